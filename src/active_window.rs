@@ -0,0 +1,36 @@
+use iced::futures::{channel::oneshot, Future};
+use std::{process::{Command, Stdio}, thread};
+
+/// `title`, asked on a helper thread since the tools it runs can take a while to answer.
+pub fn fetch_title() -> impl Future<Output = Option<String>> {
+    let (sender, title) = oneshot::channel();
+    thread::spawn(move || {
+        let _ = sender.send(self::title());
+    });
+    async move { title.await.ok().flatten() }
+}
+
+/// Title of the window in front, unless it is this app's own.
+///
+/// Asks `xdotool` on Linux, which only works under X11, and System Events through `osascript` on
+/// macOS, which gives the app's name and needs the automation permission. Elsewhere, or when the
+/// tool is missing or refuses, this is `None`.
+pub fn title() -> Option<String> {
+    let output = if cfg!(target_os = "macos") {
+        let script = "tell application \"System Events\" to get {unix id, name} of first application process whose frontmost is true";
+        run(Command::new("osascript").args(["-e", script]))?.replacen(", ", "\n", 1)
+    } else if cfg!(all(unix, not(target_os = "macos"))) {
+        run(Command::new("xdotool").args(["getactivewindow", "getwindowpid", "getwindowname"]))?
+    } else {
+        return None;
+    };
+    // Both print the process id on the first line and the name on the second
+    let (pid, title) = output.trim().split_once('\n')?;
+    let title = title.trim();
+    (pid.trim().parse() != Ok(std::process::id()) && !title.is_empty()).then(|| title.to_string())
+}
+
+fn run(command: &mut Command) -> Option<String> {
+    let output = command.stdin(Stdio::null()).stderr(Stdio::null()).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
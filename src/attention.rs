@@ -0,0 +1,26 @@
+use crate::config::Config;
+use iced::{window, Command};
+
+/// Asks for the user's attention when a session ends unnoticed: the window manager flags the
+/// window as urgent on X11 and Wayland with `urgency_hint`, and the dock icon bounces on macOS
+/// with `dock_bounce` until the app is focused. `None` when neither applies here.
+pub fn request<Message>(config: &Config) -> Option<Command<Message>> {
+    let attention = if cfg!(target_os = "macos") {
+        config.dock_bounce.then_some(window::UserAttention::Critical)
+    } else if cfg!(unix) {
+        config.urgency_hint.then_some(window::UserAttention::Informational)
+    } else {
+        None
+    };
+    Some(window::request_user_attention(window::Id::MAIN, Some(attention?)))
+}
+
+/// Withdraws a previous `request`. Window managers don't always do this on their own once the
+/// window is focused.
+pub fn clear<Message>() -> Command<Message> {
+    if cfg!(unix) {
+        window::request_user_attention(window::Id::MAIN, None)
+    } else {
+        Command::none()
+    }
+}
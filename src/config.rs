@@ -0,0 +1,706 @@
+use crate::{overrides::DurationOverrides, routine::Routine, session_log::RoundingTarget, sound::SoundTheme, technique::{Durations, Technique}, Mode};
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, fs, io, path::{Path, PathBuf}, time::Duration};
+
+/// User configuration, read from `config.toml` in the platform config directory.
+/// Every field is optional; anything missing falls back to its default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Mode buttons shown in the top row, in display order.
+    pub buttons: Vec<Mode>,
+    /// Wellness tips, one of which is shown at the start of every break.
+    pub break_tips: Vec<String>,
+    /// Also put the tip for the break in the notification that offers to start it.
+    pub tip_in_notification: bool,
+    /// Things to tick off during each break, shown as a checklist that starts over every break.
+    pub break_activities: Vec<String>,
+    /// Name of the timing technique: "classic", "52/17", "ultradian" or one from `techniques`.
+    pub technique: String,
+    /// User-defined techniques, keyed by name.
+    pub techniques: BTreeMap<String, Technique>,
+    /// Chains of sessions that run one after another once picked, keyed by name.
+    pub routines: BTreeMap<String, Routine>,
+    /// Deprecated shorthand for turning on both `auto_start_breaks` and `auto_start_work`, which
+    /// it is turned into on load. Never written back, so saving the config moves it over.
+    #[serde(skip_serializing)]
+    pub auto_cycle: bool,
+    /// Start the break that follows a work session automatically.
+    pub auto_start_breaks: bool,
+    /// Start the work session that follows a break automatically.
+    pub auto_start_work: bool,
+    /// Per-mode override of what happens when a session completes.
+    pub completion: PerMode<CompletionBehavior>,
+    /// Names shown for each mode in place of "Work", "Short break" and "Long Break".
+    pub labels: PerMode<String>,
+    /// Per-mode heading for the completion notification and screen, in place of "Work over" and
+    /// the like. `{mode}`, `{duration}`, `{count_today}` and `{task}` are filled in.
+    pub completion_messages: PerMode<String>,
+    /// Offer Start next to Resume while paused, throwing away the paused session for a fresh one.
+    pub start_from_paused_restarts: bool,
+    /// Let Start begin a running session over. Otherwise it does nothing while a session runs,
+    /// so a stray shortcut or remote command can't throw its progress away.
+    pub start_while_running_restarts: bool,
+    /// What happens to the time left on a skipped break: "forfeit" it, or "bank" it for the next break.
+    pub skipped_break_time: SkippedBreakTime,
+    /// What the Reset button leaves on the countdown: "refill_and_stop", "stop_only" or "clear".
+    pub reset_behavior: ResetBehavior,
+    /// Bank the time left on skipped work sessions, up to this many minutes, to add to a later
+    /// session. The bank empties at midnight. Zero turns it off.
+    pub time_bank_max_minutes: u64,
+    /// After this many work sessions in a day without a long break, the next break is a long
+    /// one whatever the technique says. Zero turns it off.
+    pub long_break_after: u32,
+    /// With `long_break_after`, refuse to start work until that long break has been taken.
+    pub block_work_until_long_break: bool,
+    /// Once a break has started, refuse to start work until this many seconds have gone by,
+    /// unless the break is over sooner. Zero turns it off.
+    pub min_break_seconds: u64,
+    /// Pick a random length for each work session instead of the technique's.
+    pub roulette: Option<Roulette>,
+    /// Stopping a session within this many seconds of starting it cancels it instead of logging it
+    /// as abandoned. Zero turns it off.
+    pub start_grace_seconds: u64,
+    /// Key that pauses and minimizes the window in one go, then restores and resumes on a second
+    /// press. A character, or a key name like "F9" or "Pause". Empty turns it off.
+    pub panic_key: String,
+    /// Keyboard shortcuts, per action.
+    pub keybindings: Keybindings,
+    /// When non-zero, Reset only takes effect if pressed twice within this many milliseconds.
+    pub double_press_reset_ms: u64,
+    /// Ask before a mode button throws away a running or paused session.
+    pub confirm_mode_switch: bool,
+    /// Show a short "look away" prompt every this many minutes of a work session. Zero turns it off.
+    pub micro_break_every_minutes: u64,
+    /// How long each micro-break prompt counts down. The work session keeps running meanwhile.
+    pub micro_break_seconds: u64,
+    /// Mark the window urgent when a session ends while it isn't focused (X11/Wayland).
+    pub urgency_hint: bool,
+    /// Bounce the dock icon when a session ends while the app isn't focused, until it is (macOS).
+    pub dock_bounce: bool,
+    /// Bring the window back up when a session ends while it is minimized, instead of relying on
+    /// the notification alone.
+    pub restore_on_completion: bool,
+    /// Minimize the window when a work session starts and bring it back when the break starts.
+    /// Left alone in the HUD, which already stays small and on top.
+    pub auto_minimize_on_work_start: bool,
+    /// After a session stops at 00:00, go back to a full Work session once the app has sat idle
+    /// this many minutes. Zero turns it off.
+    pub idle_reset_minutes: u64,
+    /// Wake up once a minute while the timer isn't running, so the daily counters start over at
+    /// midnight even if the app sits untouched.
+    pub idle_heartbeat: bool,
+    /// Put the window to sleep after this many minutes without a session or any input: it dims,
+    /// and stops redrawing until the mouse or a key wakes it. Zero turns it off.
+    pub sleep_after_minutes: u64,
+    /// Pause a running session when the screen is locked (Linux desktops with a screen saver on D-Bus).
+    pub pause_on_screen_lock: bool,
+    /// Resume a session paused by a screen lock once the screen is unlocked again.
+    pub resume_on_unlock: bool,
+    /// Write the time away as a gap in the session log when a running session is paused by a
+    /// screen lock, or by the app being closed.
+    pub log_away_gaps: bool,
+    /// Resume a session paused by a screen lock as soon as the window gets focus again.
+    /// Sessions paused with the Pause button are never resumed on their own.
+    pub resume_on_focus: bool,
+    /// Count down "Resuming in 3… 2… 1…" after Resume before the timer picks up again.
+    pub resume_countdown: bool,
+    /// Sound played when a session completes.
+    pub sound_theme: SoundTheme,
+    /// Audio file played when `sound_theme` is "custom".
+    pub custom_sound: String,
+    /// Sound played once when a session gets down to `warning_seconds`. Stays quiet while
+    /// `sound_theme` is "silent".
+    pub warning_sound: SoundTheme,
+    /// Audio file played when `warning_sound` is "custom".
+    pub custom_warning_sound: String,
+    /// How long before the end of a session `warning_sound` plays. Zero turns it off.
+    pub warning_seconds: u64,
+    /// Haptic buzz when a session completes, on hardware that has one. A no-op where
+    /// `haptics::SUPPORTED` is false, which for now is everywhere.
+    pub haptics: bool,
+    /// A soft click on every button press and shortcut.
+    /// Stays quiet while `sound_theme` is "silent".
+    pub click_feedback: bool,
+    /// Speak the remaining time aloud at the thresholds below. Stays quiet while `sound_theme` is "silent".
+    pub announcements: bool,
+    /// Remaining minutes at which to announce the time left.
+    pub announce_minutes: Vec<u64>,
+    /// Also announce when half of the session is over.
+    pub announce_halfway: bool,
+    /// Loopback port for the HTTP control endpoint. Needs the `http-control` feature.
+    pub control_port: Option<u16>,
+    /// Ask for confirmation before closing the window during a work session.
+    pub focus_lock: bool,
+    /// Run `focus_block_cmd` when a work session starts or resumes, and `focus_unblock_cmd` when it stops.
+    pub focus_mode: bool,
+    /// Shell command that blocks distractions, like a hosts file swap or closing chat apps.
+    pub focus_block_cmd: String,
+    /// Shell command that undoes `focus_block_cmd`. Also runs on exit if work was still running.
+    pub focus_unblock_cmd: String,
+    /// Ask for a one-line note after each completed work session, saved with it in the log.
+    pub session_notes: bool,
+    /// Without a current task, name each work session after the window in front when it
+    /// starts. The name can be edited and goes in the log as the session's note.
+    pub name_from_active_window: bool,
+    /// Write how many `break_activities` were ticked off as the note of each break in the log.
+    pub log_break_activities: bool,
+    /// Round durations written to the session log to this many minutes. Zero keeps them exact.
+    pub log_rounding_minutes: u64,
+    /// Whether rounding applies to the "planned" or "actual" duration, or "both".
+    pub log_rounding_applies_to: RoundingTarget,
+    /// Start a new `sessions.csv` once it reaches this many kilobytes. Zero never rotates on size.
+    pub log_rotate_kb: u64,
+    /// Start a new `sessions.csv` once its first session is this many days old. Zero never rotates on age.
+    pub log_rotate_days: u64,
+    /// Delete rotated logs after this many days. Zero keeps them forever.
+    pub log_keep_days: u64,
+    /// Work sessions to aim for each day, shown as a row of tomatoes. Zero hides the row.
+    pub daily_goal: u32,
+    /// Time of day, like "18:00", to send a notification summing up the day. Once a day at most.
+    pub daily_summary_at: Option<String>,
+    /// Hours of the day with no sounds or notifications. The timer keeps running as usual.
+    pub silent_hours: Option<SilentHours>,
+    /// Also send the summary when quitting, if it hasn't gone out yet and a session was completed.
+    pub daily_summary_on_quit: bool,
+    /// Hex color, like "#e5533d", for buttons and other highlights instead of the theme's blue.
+    pub accent_color: Option<String>,
+    /// Give each mode its own background, dark for work and light for breaks, instead of one
+    /// theme for everything.
+    pub mode_colors: bool,
+    /// Hex colors for the backgrounds `mode_colors` uses in place of the built-in ones. Text
+    /// turns dark on light colors and light on dark ones. Also read as `mode_backgrounds`.
+    #[serde(alias = "mode_backgrounds")]
+    pub mode_theme: PerMode<String>,
+    /// Opacity of the window background, from 0.3 to 1. Needs a compositor that supports it.
+    pub opacity: f32,
+    /// Zooms the whole window, text and spacing alike, from 0.75 to 2.
+    pub ui_scale: f32,
+    /// Show today's date and the time of day above the mode buttons.
+    pub show_clock: bool,
+    /// Show today's time in work and in breaks, the session in progress included, under the timer.
+    pub show_day_totals: bool,
+    /// During work that goes straight on into a break, also count down to the end of that break.
+    pub show_cycle_countdown: bool,
+    /// Fade the countdown in over a fraction of a second when the mode changes.
+    pub animate_transitions: bool,
+    /// Fade the countdown in over a second when a session starts. The timer counts from the
+    /// moment Start is pressed all the same.
+    pub soft_start: bool,
+    /// Shell command run as a work session starts with `soft_start`, e.g. to fade in focus music.
+    pub soft_start_cmd: String,
+    /// Open the window in the middle of the primary monitor instead of where the window manager puts it.
+    pub center_on_launch: bool,
+    /// Tick once per second, right as the shown second changes, instead of every 10 ms.
+    pub align_ticks: bool,
+    /// Show the seconds of the current minute as a ring of dots next to the countdown.
+    pub seconds_ring: bool,
+    /// Warn when today's break time falls below this fraction of work time. Zero turns it off.
+    pub min_break_ratio: f32,
+    /// Minimum gap between two break ratio warnings.
+    pub break_warning_cooldown_minutes: u64,
+    /// Also send the break ratio warning as a desktop notification.
+    pub break_warning_notification: bool,
+    /// Font family for the countdown, e.g. "DejaVu Sans Mono". Installed fonts work as-is.
+    pub timer_font: Option<String>,
+    /// Font file to load at startup that provides `timer_font`.
+    pub timer_font_file: Option<PathBuf>,
+    /// Countdown text size, kept between 24 and 300.
+    pub timer_font_size: f32,
+    /// Show the time left in words, like "about 20 min left", while a session is underway.
+    pub fuzzy_time: bool,
+    /// Session lengths from the command line and environment. Never written back to the file.
+    #[serde(skip)]
+    pub overrides: DurationOverrides,
+}
+
+/// Longest session any mode may be set to.
+pub const MAX_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Range `ui_scale` is clamped to.
+pub const MIN_UI_SCALE: f32 = 0.75;
+pub const MAX_UI_SCALE: f32 = 2.0;
+
+/// A setting that can't be used as it is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    ZeroDuration(Mode),
+    DurationTooLong(Mode, Duration),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::ZeroDuration(mode) => write!(f, "{} can't last zero minutes", mode.label()),
+            ConfigError::DurationTooLong(mode, duration) => {
+                write!(f, "{} can't last {} minutes, the most is {}", mode.label(), duration.as_secs() / 60, MAX_DURATION.as_secs() / 60)
+            }
+        }
+    }
+}
+
+/// Checks that every session length is usable: more than zero and at most `MAX_DURATION`.
+/// Whatever the durations came from, the config file, the command line or the environment, they
+/// go through here before the timer uses them.
+pub fn validate_durations(durations: &Durations) -> Result<(), ConfigError> {
+    for mode in [Mode::Work, Mode::ShortBreak, Mode::LongBreak] {
+        validate_length(mode, durations.get(mode))?;
+    }
+    Ok(())
+}
+
+/// Checks a single `mode` session length the same way. Every session the timer starts, and
+/// every length set from the timer screen, goes through here too.
+pub fn validate_length(mode: Mode, duration: Duration) -> Result<(), ConfigError> {
+    if duration.is_zero() {
+        return Err(ConfigError::ZeroDuration(mode));
+    }
+    if duration > MAX_DURATION {
+        return Err(ConfigError::DurationTooLong(mode, duration));
+    }
+    Ok(())
+}
+
+/// What happens when a session counts down to zero.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionBehavior {
+    /// Stop, showing what was finished along with what could come next.
+    Stop,
+    /// Switch to the next mode of the cycle and start it.
+    AutoNext,
+    /// Keep counting up past zero until the user starts the next session.
+    Overtime,
+    /// Sit at 00:00, with a notification, until the user starts the next session.
+    HoldUntilDismissed,
+    /// Like `Stop`, with a notification asking what to do next.
+    Ask,
+}
+
+/// Range that work session lengths are drawn from, in whole minutes.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Roulette {
+    pub min_minutes: u64,
+    pub max_minutes: u64,
+    /// Show "??:??" instead of the countdown until the session is over.
+    #[serde(default)]
+    pub hide_length: bool,
+}
+
+impl Roulette {
+    /// The bounds in order, at least a minute each and at most `MAX_DURATION`, so a swapped,
+    /// zero or huge range still works.
+    pub fn range(&self) -> (u64, u64) {
+        let most = MAX_DURATION.as_secs() / 60;
+        let (low, high) = (self.min_minutes.min(self.max_minutes), self.min_minutes.max(self.max_minutes));
+        (low.clamp(1, most), high.clamp(1, most))
+    }
+
+    /// Whether a bound goes past `MAX_DURATION`, for `range` to stop it there.
+    pub fn too_long(&self) -> bool {
+        self.min_minutes.max(self.max_minutes) > MAX_DURATION.as_secs() / 60
+    }
+}
+
+/// Times of day, like "22:00" and "07:00", between which nothing rings or notifies. An end
+/// earlier than the start is on the next day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SilentHours {
+    pub start: String,
+    pub end: String,
+}
+
+impl SilentHours {
+    /// `start` and `end` as times of day. `Err` holds the first text that couldn't be read.
+    pub fn times(&self) -> Result<(NaiveTime, NaiveTime), &str> {
+        Ok((time_of_day(&self.start)?, time_of_day(&self.end)?))
+    }
+
+    /// Whether `time` falls between `start` and `end`. Hours that can't be read are never silent.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        match self.times() {
+            Ok((start, end)) if start <= end => start <= time && time < end,
+            Ok((start, end)) => start <= time || time < end,
+            Err(_) => false,
+        }
+    }
+}
+
+/// A time of day like "18:00". `Err` holds the text that couldn't be read.
+fn time_of_day(time: &str) -> Result<NaiveTime, &str> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| time)
+}
+
+/// Key bindings, each a key with optional modifiers like "r", "ctrl+p" or "shift+Tab". Empty
+/// leaves an action unbound.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keybindings {
+    /// Start, pause or resume, whichever the main button does.
+    pub start_pause: String,
+    pub reset: String,
+    pub skip: String,
+    pub work: String,
+    pub short_break: String,
+    pub long_break: String,
+    /// Select the next mode button while idle. The arrow keys always do this too.
+    pub next_mode: String,
+    pub previous_mode: String,
+    pub hud: String,
+    pub palette: String,
+    pub settings: String,
+    /// Toggle the drift debug line.
+    pub debug: String,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Keybindings {
+            start_pause: String::from("Space"),
+            reset: String::from("r"),
+            skip: String::new(),
+            // Unbound, since one stray press would end the session in progress
+            work: String::new(),
+            short_break: String::new(),
+            long_break: String::new(),
+            next_mode: String::from("Tab"),
+            previous_mode: String::from("shift+Tab"),
+            hud: String::from("h"),
+            palette: String::from("ctrl+p"),
+            settings: String::from("ctrl+,"),
+            debug: String::from("F12"),
+        }
+    }
+}
+
+/// What becomes of the time left on a break that is skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkippedBreakTime {
+    /// It is gone.
+    Forfeit,
+    /// It is added to the next break.
+    Bank,
+}
+
+/// What Reset does to the countdown. Every one of them stops the session and starts the set over;
+/// today's totals are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetBehavior {
+    /// Put the full length back.
+    RefillAndStop,
+    /// Keep the time that was left, which the next Start counts down from.
+    StopOnly,
+    /// Go to 00:00. The next Start is a full session.
+    Clear,
+}
+
+/// A value that can be set separately for each mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PerMode<T> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub work: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short_break: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub long_break: Option<T>,
+}
+
+impl<T> Default for PerMode<T> {
+    fn default() -> Self {
+        PerMode { work: None, short_break: None, long_break: None }
+    }
+}
+
+impl<T> PerMode<T> {
+    pub fn get(&self, mode: Mode) -> Option<&T> {
+        match mode {
+            Mode::Work => self.work.as_ref(),
+            Mode::ShortBreak => self.short_break.as_ref(),
+            Mode::LongBreak => self.long_break.as_ref(),
+        }
+    }
+}
+
+const DEFAULT_BREAK_TIPS: &[&str] = &[
+    "Stretch your neck",
+    "Look at something 20 feet away for 20 seconds",
+    "Stand up and walk around",
+    "Drink a glass of water",
+    "Roll your shoulders",
+    "Close your eyes and take a few deep breaths",
+];
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            buttons: vec![Mode::Work, Mode::ShortBreak, Mode::LongBreak],
+            break_tips: DEFAULT_BREAK_TIPS.iter().map(|tip| tip.to_string()).collect(),
+            tip_in_notification: false,
+            break_activities: Vec::new(),
+            technique: String::from("classic"),
+            techniques: BTreeMap::new(),
+            routines: BTreeMap::new(),
+            auto_cycle: false,
+            auto_start_breaks: false,
+            auto_start_work: false,
+            completion: PerMode::default(),
+            labels: PerMode::default(),
+            completion_messages: PerMode::default(),
+            start_from_paused_restarts: false,
+            start_while_running_restarts: false,
+            skipped_break_time: SkippedBreakTime::Forfeit,
+            reset_behavior: ResetBehavior::RefillAndStop,
+            time_bank_max_minutes: 0,
+            long_break_after: 0,
+            block_work_until_long_break: false,
+            min_break_seconds: 0,
+            roulette: None,
+            start_grace_seconds: 0,
+            panic_key: String::from("F9"),
+            keybindings: Keybindings::default(),
+            double_press_reset_ms: 0,
+            confirm_mode_switch: false,
+            micro_break_every_minutes: 0,
+            micro_break_seconds: 20,
+            urgency_hint: true,
+            dock_bounce: false,
+            restore_on_completion: false,
+            auto_minimize_on_work_start: false,
+            idle_reset_minutes: 0,
+            idle_heartbeat: false,
+            sleep_after_minutes: 0,
+            pause_on_screen_lock: false,
+            resume_on_unlock: true,
+            log_away_gaps: false,
+            resume_on_focus: false,
+            resume_countdown: false,
+            sound_theme: SoundTheme::Bell,
+            custom_sound: String::new(),
+            warning_sound: SoundTheme::Silent,
+            custom_warning_sound: String::new(),
+            warning_seconds: 60,
+            haptics: false,
+            click_feedback: false,
+            announcements: false,
+            announce_minutes: vec![5, 1],
+            announce_halfway: true,
+            control_port: None,
+            focus_lock: false,
+            focus_mode: false,
+            focus_block_cmd: String::new(),
+            focus_unblock_cmd: String::new(),
+            session_notes: false,
+            name_from_active_window: false,
+            log_break_activities: false,
+            log_rounding_minutes: 0,
+            log_rounding_applies_to: RoundingTarget::Both,
+            log_rotate_kb: 0,
+            log_rotate_days: 0,
+            log_keep_days: 0,
+            daily_goal: 8,
+            daily_summary_at: None,
+            silent_hours: None,
+            daily_summary_on_quit: false,
+            accent_color: None,
+            mode_colors: false,
+            mode_theme: PerMode::default(),
+            opacity: 1.0,
+            ui_scale: 1.0,
+            show_clock: false,
+            show_day_totals: false,
+            show_cycle_countdown: false,
+            animate_transitions: false,
+            soft_start: false,
+            soft_start_cmd: String::new(),
+            center_on_launch: false,
+            align_ticks: false,
+            seconds_ring: false,
+            min_break_ratio: 0.0,
+            break_warning_cooldown_minutes: 60,
+            break_warning_notification: false,
+            timer_font: None,
+            timer_font_file: None,
+            timer_font_size: 120.0,
+            fuzzy_time: false,
+            overrides: DurationOverrides::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config file, falling back to defaults if it is missing or invalid, or if there
+    /// is no config file to load.
+    pub fn load(path: Option<&Path>) -> Config {
+        let Some(path) = path else {
+            return Config::default();
+        };
+        let config = match fs::read_to_string(path) {
+            Ok(contents) => toml::from_str(&contents).unwrap_or_else(|err| {
+                eprintln!("Ignoring invalid config {}: {}", path.display(), err);
+                Config::default()
+            }),
+            Err(_) => Config::default(),
+        };
+        config.normalized()
+    }
+
+    /// Writes the config back to disk, e.g. after a change in the settings screen. A file there
+    /// that doesn't load is copied to `config.toml.invalid` first, so the user's edits aren't lost.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        if let Ok(existing) = fs::read_to_string(path) {
+            if toml::from_str::<Config>(&existing).is_err() {
+                let mut backup = path.as_os_str().to_owned();
+                backup.push(".invalid");
+                fs::write(&backup, existing)?;
+                eprintln!("Kept the config that couldn't be loaded as {}", Path::new(&backup).display());
+            }
+        }
+        let contents = toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(path, contents)
+    }
+
+    /// Completion behavior for `finished`, falling back to whether `next` starts automatically.
+    pub fn completion(&self, finished: Mode, next: Mode) -> CompletionBehavior {
+        self.completion.get(finished).copied().unwrap_or(if self.auto_start(next) { CompletionBehavior::AutoNext } else { CompletionBehavior::Stop })
+    }
+
+    /// `daily_summary_at` as a time of day. `Err` holds the text that couldn't be read.
+    pub fn daily_summary_time(&self) -> Result<Option<NaiveTime>, &str> {
+        match self.daily_summary_at.as_deref() {
+            Some(time) => time_of_day(time).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The name shown for `mode`, from `labels` when set.
+    pub fn label_for(&self, mode: Mode) -> &str {
+        self.labels.get(mode).map_or(mode.label(), String::as_str)
+    }
+
+    /// Whether a `mode` session starts by itself once the one before it completes.
+    pub fn auto_start(&self, mode: Mode) -> bool {
+        if mode.is_break() { self.auto_start_breaks } else { self.auto_start_work }
+    }
+
+    /// Resolves the selected technique, preferring a custom definition over a built-in of the same name.
+    /// Duration overrides are applied on top.
+    pub fn technique(&self) -> Technique {
+        let mut technique = self.techniques.get(&self.technique).cloned().or_else(|| Technique::preset(&self.technique)).unwrap_or_else(|| {
+            eprintln!("Unknown technique {:?}, using classic", self.technique);
+            Technique::classic()
+        });
+        self.overrides.apply(&mut technique);
+        technique
+    }
+
+    fn normalized(mut self) -> Config {
+        let mut seen = Vec::new();
+        self.buttons.retain(|mode| {
+            let first = !seen.contains(mode);
+            seen.push(*mode);
+            first
+        });
+        if self.buttons.is_empty() {
+            self.buttons.push(Mode::Work);
+        }
+        self.timer_font_size = self.timer_font_size.clamp(24.0, 300.0);
+        self.ui_scale = self.ui_scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+        if self.auto_cycle {
+            self.auto_start_breaks = true;
+            self.auto_start_work = true;
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn durations(work: Duration, short_break: Duration, long_break: Duration) -> Durations {
+        Durations { work, short_break, long_break }
+    }
+
+    fn minutes(minutes: u64) -> Duration {
+        Duration::from_secs(minutes * 60)
+    }
+
+    #[test]
+    fn accepts_the_presets() {
+        for technique in [Technique::classic(), Technique::fifty_two_seventeen(), Technique::ultradian()] {
+            assert_eq!(validate_durations(&technique.durations()), Ok(()));
+        }
+    }
+
+    #[test]
+    fn rejects_zero() {
+        assert_eq!(validate_durations(&durations(minutes(25), Duration::ZERO, minutes(15))), Err(ConfigError::ZeroDuration(Mode::ShortBreak)));
+    }
+
+    #[test]
+    fn rejects_huge_values() {
+        let huge = Technique { work_minutes: u64::MAX, ..Technique::classic() }.durations();
+        assert!(matches!(validate_durations(&huge), Err(ConfigError::DurationTooLong(Mode::Work, _))));
+        assert!(matches!(validate_durations(&durations(minutes(25), minutes(5), Duration::MAX)), Err(ConfigError::DurationTooLong(Mode::LongBreak, _))));
+    }
+
+    #[test]
+    fn saving_over_a_config_that_did_not_load_keeps_a_copy() {
+        let dir = std::env::temp_dir().join(format!("rusty-pomodoro-config-{}", std::process::id()));
+        let path = dir.join("config.toml");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "daily_goal = \"eight\"\n").unwrap();
+        let config = Config::load(Some(&path));
+        config.save(&path).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("config.toml.invalid")).unwrap(), "daily_goal = \"eight\"\n");
+        assert!(toml::from_str::<Config>(&fs::read_to_string(&path).unwrap()).is_ok());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn auto_cycle_loads_as_both_auto_starts_and_is_not_saved() {
+        let config = toml::from_str::<Config>("auto_cycle = true\n").unwrap().normalized();
+        assert!(config.auto_start_breaks && config.auto_start_work);
+        assert_eq!(config.completion(Mode::Work, Mode::ShortBreak), CompletionBehavior::AutoNext);
+        let saved = toml::to_string(&config).unwrap();
+        assert!(!saved.contains("auto_cycle") && saved.contains("auto_start_work = true"));
+    }
+
+    #[test]
+    fn mode_theme_is_also_read_under_its_old_name() {
+        for table in ["mode_theme", "mode_backgrounds"] {
+            let config = toml::from_str::<Config>(&format!("mode_colors = true\n[{}]\nwork = \"#14161c\"\n", table)).unwrap();
+            assert_eq!(config.mode_theme.get(Mode::Work).map(String::as_str), Some("#14161c"), "{}", table);
+        }
+    }
+
+    #[test]
+    fn silent_hours_can_run_past_midnight() {
+        let at = |time: &str| NaiveTime::parse_from_str(time, "%H:%M").unwrap();
+        let night = SilentHours { start: String::from("22:00"), end: String::from("07:00") };
+        assert!(night.contains(at("22:00")) && night.contains(at("03:30")));
+        assert!(!night.contains(at("07:00")) && !night.contains(at("12:00")));
+        let lunch = SilentHours { start: String::from("12:00"), end: String::from("13:00") };
+        assert!(lunch.contains(at("12:30")) && !lunch.contains(at("21:00")));
+        let typo = SilentHours { start: String::from("22:00"), end: String::from("7") };
+        assert_eq!(typo.times(), Err("7"));
+        assert!(!typo.contains(at("23:00")));
+    }
+
+    #[test]
+    fn boundaries_are_inclusive() {
+        let second = Duration::from_secs(1);
+        assert_eq!(validate_durations(&durations(second, second, MAX_DURATION)), Ok(()));
+        assert_eq!(
+            validate_durations(&durations(MAX_DURATION + second, second, second)),
+            Err(ConfigError::DurationTooLong(Mode::Work, MAX_DURATION + second))
+        );
+    }
+}
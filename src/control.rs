@@ -0,0 +1,150 @@
+//! Tiny HTTP listener on the loopback interface for scripting the timer, e.g.
+//! `curl -X POST localhost:7878/start`. Only built with the `http-control` feature.
+use crate::{PauseReason, Pomodoro, PomodoroMessage, State};
+use iced::{futures::{channel::mpsc, SinkExt, StreamExt}, subscription, Subscription};
+use std::{fmt, io::{self, BufRead, BufReader, Write}, net::{Ipv4Addr, TcpListener, TcpStream}, sync::{self, Arc, Mutex}, thread, time::Duration};
+
+#[derive(Debug, Clone, Copy)]
+pub enum Action {
+    Start,
+    Pause,
+    Reset,
+}
+
+/// An action received over HTTP, along with the way back to the waiting connection.
+#[derive(Clone)]
+pub struct Request {
+    pub action: Action,
+    reply: Arc<Mutex<Option<sync::mpsc::Sender<String>>>>,
+}
+
+impl Request {
+    /// Sends the JSON state back to the client. Only the first reply is delivered.
+    pub fn reply(&self, json: String) {
+        if let Some(reply) = self.reply.lock().expect("reply lock").take() {
+            let _ = reply.send(json);
+        }
+    }
+}
+
+impl fmt::Debug for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Request").field("action", &self.action).finish()
+    }
+}
+
+impl Pomodoro {
+    pub(crate) fn control(&mut self, request: Request) {
+        match (request.action, &self.state) {
+            (Action::Start, State::Idle | State::Overtime | State::Held | State::Completed { .. }) => self.start(),
+            (Action::Start, State::Paused(_) | State::Resuming { .. }) => self.resume(),
+            (Action::Pause, State::Running | State::Resuming { .. }) => self.pause(PauseReason::Manual),
+            (Action::Reset, _) => self.reset(self.config.reset_behavior),
+            _ => {}
+        }
+        request.reply(self.status_json());
+    }
+
+    fn status_json(&self) -> String {
+        let remaining = self.remaining_shown().map_or(String::from("null"), |left| left.as_secs().to_string());
+        format!(r#"{{"state":"{}","mode":"{}","remaining_secs":{}}}"#, self.state.key(), self.mode.key(), remaining)
+    }
+}
+
+pub fn listen(port: u16) -> Subscription<PomodoroMessage> {
+    subscription::channel(port, 8, move |mut output| async move {
+        let (sender, mut requests) = mpsc::unbounded();
+        match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+            Ok(listener) => {
+                thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        if let Err(err) = handle(stream, &sender) {
+                            eprintln!("Control request failed: {}", err);
+                        }
+                    }
+                });
+            }
+            Err(err) => eprintln!("Could not listen on port {}: {}", port, err),
+        }
+        while let Some(request) = requests.next().await {
+            let _ = output.send(PomodoroMessage::Control(request)).await;
+        }
+        iced::futures::future::pending().await
+    })
+}
+
+fn handle(mut stream: TcpStream, sender: &mpsc::UnboundedSender<Request>) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // None of the routes take a body, so the headers are the rest of the request.
+    let (mut origin, mut host) = (None, None);
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':') {
+            match name.trim().to_ascii_lowercase().as_str() {
+                "origin" => origin = Some(value.trim().to_string()),
+                "host" => host = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+        header.clear();
+    }
+    if !allowed(origin.as_deref(), host.as_deref()) {
+        return respond(&mut stream, "403 Forbidden", r#"{"error":"only local programs may control the timer"}"#);
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let action = match (parts.next(), parts.next()) {
+        (Some("POST"), Some("/start")) => Action::Start,
+        (Some("POST"), Some("/pause")) => Action::Pause,
+        (Some("POST"), Some("/reset")) => Action::Reset,
+        (Some("POST"), _) => return respond(&mut stream, "404 Not Found", r#"{"error":"unknown action"}"#),
+        _ => return respond(&mut stream, "405 Method Not Allowed", r#"{"error":"use POST"}"#),
+    };
+    let (reply, state) = sync::mpsc::channel();
+    let _ = sender.unbounded_send(Request { action, reply: Arc::new(Mutex::new(Some(reply))) });
+    match state.recv_timeout(Duration::from_secs(2)) {
+        Ok(json) => respond(&mut stream, "200 OK", &json),
+        Err(_) => respond(&mut stream, "503 Service Unavailable", r#"{"error":"no response from timer"}"#),
+    }
+}
+
+/// Whether a request may come from a web page on another site. Browsers send `Origin` with
+/// their POSTs, and a page that rebinds its own name to 127.0.0.1 still carries it in `Host`;
+/// scripts like curl send a local `Host` and no `Origin`.
+fn allowed(origin: Option<&str>, host: Option<&str>) -> bool {
+    let origin = origin.map(|origin| origin.split_once("://").map_or(origin, |(_, authority)| authority));
+    origin.is_none_or(is_local) && host.is_none_or(is_local)
+}
+
+/// Whether a `host[:port]` names this machine.
+fn is_local(authority: &str) -> bool {
+    let host = match authority.strip_prefix('[') {
+        Some(v6) => v6.split_once(']').map_or(v6, |(host, _)| host),
+        None => authority.split_once(':').map_or(authority, |(host, _)| host),
+    };
+    host.eq_ignore_ascii_case("localhost") || host == "127.0.0.1" || host == "::1"
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write!(stream, "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_requests_from_this_machine_are_allowed() {
+        assert!(allowed(None, None));
+        assert!(allowed(None, Some("localhost:7878")));
+        assert!(allowed(Some("http://127.0.0.1:7878"), Some("127.0.0.1:7878")));
+        assert!(allowed(Some("http://[::1]:7878"), Some("[::1]:7878")));
+        assert!(!allowed(Some("https://example.com"), Some("localhost:7878")));
+        assert!(!allowed(Some("null"), Some("localhost:7878")));
+        assert!(!allowed(None, Some("rebound.example.com:7878")));
+        assert!(!allowed(Some("http://localhost.example.com"), Some("localhost:7878")));
+    }
+}
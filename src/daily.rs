@@ -0,0 +1,230 @@
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fs, io, path::{Path, PathBuf}};
+
+/// Counters that start over at local midnight.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyCounters {
+    /// Work sessions started today; the current one is this number.
+    pub pomodoros_started: u32,
+    /// Work sessions that ran all the way to 00:00 today.
+    pub pomodoros_completed: u32,
+    /// Seconds spent in work sessions today, finished or not.
+    pub work_secs: u64,
+    /// Seconds spent in breaks today.
+    pub break_secs: u64,
+    /// Work sessions completed today without a single pause.
+    pub perfect_sessions: u32,
+    /// Time left on work sessions skipped today, for `time_bank_max_minutes`.
+    pub banked_secs: u64,
+    /// Work sessions completed today since the last long break, for `long_break_after`.
+    pub work_since_long_break: u32,
+    /// Whether today's summary notification has gone out.
+    pub summary_sent: bool,
+}
+
+/// Days in a row with at least one completed work session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Streak {
+    pub days: u32,
+    /// The last day that counted towards the streak.
+    pub last_day: Option<NaiveDate>,
+}
+
+/// Today's counters along with the day they belong to, persisted in `daily.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Daily {
+    pub date: NaiveDate,
+    pub counters: DailyCounters,
+    #[serde(default)]
+    pub streak: Streak,
+}
+
+impl Default for Daily {
+    fn default() -> Self {
+        Daily { date: Local::now().date_naive(), counters: DailyCounters::default(), streak: Streak::default() }
+    }
+}
+
+impl Daily {
+    fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join("daily.toml")
+    }
+
+    pub fn load(data_dir: &Path) -> Daily {
+        let mut daily: Daily = fs::read_to_string(Daily::path(data_dir))
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).map_err(|err| eprintln!("Ignoring invalid daily counters: {}", err)).ok())
+            .unwrap_or_default();
+        daily.roll_over();
+        daily
+    }
+
+    pub fn save(&self, data_dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(data_dir)?;
+        let contents = toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(Daily::path(data_dir), contents)
+    }
+
+    /// Counts today towards the streak, after a completed work session.
+    pub fn extend_streak(&mut self) {
+        let continued = self.streak.last_day.is_some_and(|day| day.succ_opt() == Some(self.date));
+        match self.streak.last_day {
+            Some(day) if day == self.date => {}
+            _ if continued => self.streak.days += 1,
+            _ => self.streak.days = 1,
+        }
+        self.streak.last_day = Some(self.date);
+    }
+
+    /// Sets the streak from the days with completed work in `worked`, when the run of them that
+    /// ends today or yesterday is longer than the streak already counted.
+    pub fn recount_streak(&mut self, worked: &HashSet<NaiveDate>) {
+        let Some(last) = [Some(self.date), self.date.pred_opt()].into_iter().flatten().find(|day| worked.contains(day)) else {
+            return;
+        };
+        let days = last.iter_days().rev().take_while(|day| worked.contains(day)).count() as u32;
+        if days > self.current_streak() {
+            self.streak = Streak { days, last_day: Some(last) };
+        }
+    }
+
+    /// The streak as of today: still alive if yesterday counted, even before today's first session.
+    pub fn current_streak(&self) -> u32 {
+        match self.streak.last_day {
+            Some(day) if day == self.date || day.succ_opt() == Some(self.date) => self.streak.days,
+            _ => 0,
+        }
+    }
+
+    /// One line about the day, e.g. "Today: 6 pomodoros, 2h30m focused, 5-day streak!".
+    pub fn summary(&self) -> String {
+        let focused = hours_minutes(self.counters.work_secs);
+        let pomodoros = match self.counters.pomodoros_completed {
+            1 => String::from("1 pomodoro"),
+            n => format!("{} pomodoros", n),
+        };
+        match self.current_streak() {
+            0 | 1 => format!("Today: {}, {} focused.", pomodoros, focused),
+            days => format!("Today: {}, {} focused, {}-day streak!", pomodoros, focused, days),
+        }
+    }
+
+    /// Clears the counters if the local date has moved on since they were last touched.
+    pub fn roll_over(&mut self) {
+        self.roll_over_at(Local::now());
+    }
+
+    /// Like `roll_over`, as of `now`, e.g. the moment a session ended.
+    pub fn roll_over_at<Tz: TimeZone>(&mut self, now: DateTime<Tz>) {
+        maybe_reset_daily(now, &mut self.date, &mut self.counters);
+    }
+}
+
+/// A length of time like "2h30m", or "40m" under an hour.
+pub fn hours_minutes(secs: u64) -> String {
+    let minutes = secs / 60;
+    match minutes / 60 {
+        0 => format!("{}m", minutes),
+        hours => format!("{}h{:0>2}m", hours, minutes % 60),
+    }
+}
+
+/// Starts `counters` over when `now` falls on a later calendar day, in its own time zone, than
+/// `last_reset`. A date that goes backwards, say after flying west across midnight, keeps the
+/// counters as they are rather than wiping a day that is still going.
+pub fn maybe_reset_daily<Tz: TimeZone>(now: DateTime<Tz>, last_reset: &mut NaiveDate, counters: &mut DailyCounters) {
+    let today = now.date_naive();
+    if today > *last_reset {
+        *last_reset = today;
+        *counters = DailyCounters::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn at(offset_hours: i32, date: (i32, u32, u32), time: (u32, u32)) -> DateTime<FixedOffset> {
+        FixedOffset::east_opt(offset_hours * 3600).unwrap().with_ymd_and_hms(date.0, date.1, date.2, time.0, time.1, 0).unwrap()
+    }
+
+    fn day(date: (i32, u32, u32)) -> NaiveDate {
+        NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap()
+    }
+
+    fn counters() -> DailyCounters {
+        DailyCounters { pomodoros_started: 5, pomodoros_completed: 4, work_secs: 7500, break_secs: 1500, perfect_sessions: 2, banked_secs: 600, summary_sent: true, work_since_long_break: 3 }
+    }
+
+    #[test]
+    fn session_ending_just_before_midnight_counts_to_that_day() {
+        let (mut last_reset, mut counters) = (day((2024, 3, 14)), counters());
+        maybe_reset_daily(at(1, (2024, 3, 14), (23, 59)), &mut last_reset, &mut counters);
+        assert_eq!(last_reset, day((2024, 3, 14)));
+        assert_eq!(counters, self::counters());
+    }
+
+    #[test]
+    fn session_ending_just_after_midnight_starts_a_new_day() {
+        let (mut last_reset, mut counters) = (day((2024, 3, 14)), counters());
+        maybe_reset_daily(at(1, (2024, 3, 15), (0, 1)), &mut last_reset, &mut counters);
+        assert_eq!(last_reset, day((2024, 3, 15)));
+        assert_eq!(counters, DailyCounters::default());
+    }
+
+    /// Adds a work session that ended at `ended` the way `record_daily` does, and returns the
+    /// day the counters are for along with the work counted on it.
+    fn log(daily: &mut Daily, ended: DateTime<FixedOffset>, secs: u64) -> (NaiveDate, u64) {
+        daily.roll_over_at(ended);
+        daily.counters.work_secs += secs;
+        (daily.date, daily.counters.work_secs)
+    }
+
+    #[test]
+    fn midnight_is_found_across_a_dst_change() {
+        // São Paulo in 2018 went from 23:59 at -03:00 straight to 01:00 at -02:00, a minute later
+        let mut daily = Daily { date: day((2018, 11, 3)), ..Daily::default() };
+        assert_eq!(log(&mut daily, at(-3, (2018, 11, 3), (23, 30)), 1500), (day((2018, 11, 3)), 1500));
+        assert_eq!(log(&mut daily, at(-3, (2018, 11, 3), (23, 59)), 60), (day((2018, 11, 3)), 1560));
+        assert_eq!(log(&mut daily, at(-2, (2018, 11, 4), (1, 0)), 60), (day((2018, 11, 4)), 60));
+    }
+
+    #[test]
+    fn falling_back_across_midnight_keeps_the_day_until_it_ends() {
+        // São Paulo in 2019 went from 23:59 at -02:00 back to 23:00 at -03:00, so 23:xx came twice
+        let mut daily = Daily { date: day((2019, 2, 16)), ..Daily::default() };
+        assert_eq!(log(&mut daily, at(-2, (2019, 2, 16), (23, 50)), 600), (day((2019, 2, 16)), 600));
+        assert_eq!(log(&mut daily, at(-3, (2019, 2, 16), (23, 10)), 1200), (day((2019, 2, 16)), 1800));
+        assert_eq!(log(&mut daily, at(-3, (2019, 2, 17), (0, 10)), 600), (day((2019, 2, 17)), 600));
+    }
+
+    #[test]
+    fn dst_change_within_the_day_keeps_the_counters() {
+        // Central Europe fell back from 03:00 at +02:00 to 02:00 at +01:00, in the middle of the day
+        let mut daily = Daily { date: day((2024, 10, 27)), ..Daily::default() };
+        assert_eq!(log(&mut daily, at(2, (2024, 10, 27), (2, 50)), 1500), (day((2024, 10, 27)), 1500));
+        assert_eq!(log(&mut daily, at(1, (2024, 10, 27), (2, 20)), 1500), (day((2024, 10, 27)), 3000));
+        assert_eq!(log(&mut daily, at(1, (2024, 10, 27), (23, 59)), 60), (day((2024, 10, 27)), 3060));
+    }
+
+    #[test]
+    fn moving_east_past_midnight_starts_a_new_day() {
+        // 22:00 in UTC is already 07:00 the next day in UTC+9
+        let (mut last_reset, mut counters) = (day((2024, 6, 1)), counters());
+        maybe_reset_daily(at(9, (2024, 6, 2), (7, 0)), &mut last_reset, &mut counters);
+        assert_eq!(last_reset, day((2024, 6, 2)));
+        assert_eq!(counters, DailyCounters::default());
+    }
+
+    #[test]
+    fn moving_west_back_across_midnight_keeps_the_counters() {
+        let (mut last_reset, mut counters) = (day((2024, 6, 2)), counters());
+        maybe_reset_daily(at(-8, (2024, 6, 1), (17, 0)), &mut last_reset, &mut counters);
+        assert_eq!(last_reset, day((2024, 6, 2)));
+        assert_eq!(counters, self::counters());
+    }
+}
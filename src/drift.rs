@@ -0,0 +1,39 @@
+use std::time::{Duration, Instant};
+
+/// Compares how much the timer has counted down against the wall-clock time it spent running,
+/// to check that the delta-based ticking doesn't lose or gain time.
+#[derive(Default)]
+pub struct Drift {
+    /// Sum of the deltas actually taken off the timer.
+    counted: Duration,
+    /// Wall-clock time spent running, not including the current stretch.
+    measured: Duration,
+    running_since: Option<Instant>,
+}
+
+impl Drift {
+    /// Starts measuring a fresh session.
+    pub fn start(&mut self, now: Instant) {
+        *self = Drift { running_since: Some(now), ..Drift::default() };
+    }
+
+    pub fn resume(&mut self, now: Instant) {
+        self.running_since = Some(now);
+    }
+
+    pub fn pause(&mut self, now: Instant) {
+        if let Some(since) = self.running_since.take() {
+            self.measured += now - since;
+        }
+    }
+
+    pub fn count(&mut self, delta: Duration) {
+        self.counted += delta;
+    }
+
+    /// Seconds the timer has counted beyond the measured time; negative when it lags behind.
+    pub fn seconds(&self, now: Instant) -> f64 {
+        let measured = self.measured + self.running_since.map(|since| now - since).unwrap_or_default();
+        self.counted.as_secs_f64() - measured.as_secs_f64()
+    }
+}
@@ -0,0 +1,111 @@
+use std::{io, process, sync::mpsc, thread, time::{Duration, Instant}};
+
+/// How long a focus command gets before it is killed, so a stuck script can't hold up the
+/// ones queued after it.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn shell(command: &str) -> process::Command {
+    let mut shell = if cfg!(windows) { process::Command::new("cmd") } else { process::Command::new("sh") };
+    shell.arg(if cfg!(windows) { "/C" } else { "-c" }).arg(command);
+    shell.stdin(process::Stdio::null());
+    shell
+}
+
+/// Starts a command like `soft_start_cmd` through the shell without waiting for it. Empty
+/// commands are skipped.
+pub fn spawn(command: &str) {
+    if command.trim().is_empty() {
+        return;
+    }
+    match shell(command).spawn() {
+        Ok(mut child) => {
+            // Reaped on a helper thread so a slow script doesn't hold up the UI
+            thread::spawn(move || child.wait());
+        }
+        Err(err) => eprintln!("Could not run `{}`: {}", command, err),
+    }
+}
+
+/// Runs `focus_block_cmd` and `focus_unblock_cmd` one at a time and in order on a helper
+/// thread, so an unblock can't finish before the block it undoes.
+#[derive(Default)]
+pub struct Queue {
+    worker: Option<(mpsc::Sender<String>, mpsc::Receiver<()>)>,
+}
+
+impl Queue {
+    /// Adds a command after the ones already queued, starting the helper thread on first use.
+    /// Empty commands are skipped.
+    pub fn push(&mut self, command: &str) {
+        if command.trim().is_empty() {
+            return;
+        }
+        let (commands, _) = self.worker.get_or_insert_with(|| {
+            let (commands, queued) = mpsc::channel::<String>();
+            let (done, finished) = mpsc::channel();
+            thread::spawn(move || {
+                for command in queued {
+                    if let Err(err) = run(&command) {
+                        eprintln!("Could not run `{}`: {}", command, err);
+                    }
+                }
+                let _ = done.send(());
+            });
+            (commands, finished)
+        });
+        let _ = commands.send(command.to_string());
+    }
+
+    /// Waits up to `timeout` for the queued commands to finish, for quitting. Returns whether
+    /// they did.
+    pub fn finish(self, timeout: Duration) -> bool {
+        match self.worker {
+            Some((commands, finished)) => {
+                drop(commands);
+                finished.recv_timeout(timeout).is_ok()
+            }
+            None => true,
+        }
+    }
+}
+
+/// Runs a command to completion, killing it after `TIMEOUT`. A non-zero exit is an error.
+fn run(command: &str) -> io::Result<()> {
+    let mut child = shell(command).spawn()?;
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return match status.success() {
+                true => Ok(()),
+                false => Err(io::Error::other(format!("exited with {}", status))),
+            };
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(io::ErrorKind::TimedOut, format!("still running after {} seconds", TIMEOUT.as_secs())));
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn queued_commands_run_in_order_and_failures_are_errors() {
+        let out = std::env::temp_dir().join(format!("rusty-pomodoro-focus-{}", std::process::id()));
+        let _ = std::fs::remove_file(&out);
+        let mut queue = Queue::default();
+        queue.push(&format!("sleep 0.2; echo block >> {}", out.display()));
+        queue.push(&format!("echo unblock >> {}", out.display()));
+        assert!(queue.finish(Duration::from_secs(5)));
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "block\nunblock\n");
+        let _ = std::fs::remove_file(&out);
+
+        assert!(run("exit 0").is_ok());
+        assert!(run("exit 3").is_err());
+    }
+}
@@ -0,0 +1,32 @@
+use crate::{config::Config, PomodoroMessage};
+use iced::{font, Command, Font};
+use std::fs;
+
+/// Resolves the configured timer font. A font file is loaded into iced first; if it can't be read
+/// or isn't a font, the default font is used instead.
+pub fn timer_font(config: &Config) -> (Font, Command<PomodoroMessage>) {
+    let Some(family) = &config.timer_font else {
+        return (Font::DEFAULT, Command::none());
+    };
+    // iced refers to font families by `&'static str`, and this is resolved once per run.
+    let font = Font::with_name(Box::leak(family.clone().into_boxed_str()));
+    let Some(path) = &config.timer_font_file else {
+        return (font, Command::none());
+    };
+    match fs::read(path) {
+        Ok(bytes) if is_font(&bytes) => (font, font::load(bytes).map(|_| PomodoroMessage::FontLoaded)),
+        Ok(_) => {
+            eprintln!("{} is not a TrueType or OpenType font, using the default", path.display());
+            (Font::DEFAULT, Command::none())
+        }
+        Err(err) => {
+            eprintln!("Could not read font {}: {}, using the default", path.display(), err);
+            (Font::DEFAULT, Command::none())
+        }
+    }
+}
+
+/// Checks for one of the sfnt signatures that start TrueType, OpenType and collection files.
+fn is_font(bytes: &[u8]) -> bool {
+    matches!(bytes.get(..4), Some([0, 1, 0, 0] | b"OTTO" | b"true" | b"ttcf"))
+}
@@ -0,0 +1,8 @@
+/// Whether this build can reach a haptic motor. Neither iced nor winit expose haptic feedback,
+/// and the desktop platforms only offer it through native toolkit calls, so no platform is
+/// supported yet.
+pub const SUPPORTED: bool = false;
+
+/// Buzzes the device's haptic motor where `SUPPORTED`, and does nothing elsewhere. It never
+/// fails, so callers don't need to check for support first.
+pub fn buzz() {}
@@ -0,0 +1,68 @@
+use crate::{session_log::WorkDay, Pomodoro, PomodoroMessage, Screen};
+use chrono::{Datelike, Duration as Days, NaiveDate};
+use iced::{mouse, widget::{canvas::{self, Geometry, Path}, Button, Canvas, Column, Container, Text}, Color, Element, Length, Point, Rectangle, Renderer, Size, Theme};
+use std::collections::HashMap;
+
+/// Weeks shown, the current one last.
+pub const WEEKS: i64 = 20;
+const CELL: f32 = 14.0;
+const GAP: f32 = 3.0;
+/// Completed pomodoros a day needs for each shade after the empty one.
+const LEVELS: [u32; 4] = [1, 3, 6, 9];
+
+/// The Monday the grid starts on, so that `today`'s week is the last column.
+pub fn first_day(today: NaiveDate) -> NaiveDate {
+    today - Days::days(i64::from(today.weekday().num_days_from_monday()) + (WEEKS - 1) * 7)
+}
+
+/// Shade of a day from 0, for none, to 4, for `LEVELS`' last step and up.
+fn level(count: u32) -> usize {
+    LEVELS.iter().filter(|&&step| count >= step).count()
+}
+
+/// A GitHub-style grid of days, one column per week with Monday on top, shaded by the number
+/// of completed work sessions. Days without any are drawn faintly, days after `today` not at all.
+struct Grid<'a> {
+    days: &'a HashMap<NaiveDate, WorkDay>,
+    today: NaiveDate,
+}
+
+impl<'a, Message> canvas::Program<Message> for Grid<'a> {
+    type State = ();
+
+    fn draw(&self, _state: &(), renderer: &Renderer, theme: &Theme, bounds: Rectangle, _cursor: mouse::Cursor) -> Vec<Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let palette = theme.palette();
+        let first = first_day(self.today);
+        for (i, day) in first.iter_days().take_while(|day| *day <= self.today).enumerate() {
+            let (week, weekday) = (i / 7, i % 7);
+            let position = Point::new(week as f32 * (CELL + GAP), weekday as f32 * (CELL + GAP));
+            let color = match level(self.days.get(&day).map_or(0, |day| day.completed)) {
+                0 => Color { a: 0.08, ..palette.text },
+                shade => Color { a: [0.3, 0.55, 0.8, 1.0][shade - 1], ..palette.primary },
+            };
+            frame.fill(&Path::rectangle(position, Size::new(CELL, CELL)), color);
+        }
+        vec![frame.into_geometry()]
+    }
+}
+
+impl Pomodoro {
+    pub(crate) fn heatmap_view(&self) -> Element<'_, PomodoroMessage> {
+        let back_button = Button::new("Back").on_press(PomodoroMessage::ShowScreen(Screen::Settings).pressed());
+        let col = Column::new().spacing(15).padding(20);
+        let col = match &self.heatmap {
+            Ok(days) => {
+                let today = self.daily.date;
+                let shown = days.iter().filter(|(day, _)| **day >= first_day(today)).map(|(_, counts)| counts);
+                let (total, perfect) = shown.fold((0, 0), |(total, perfect), counts| (total + counts.completed, perfect + counts.perfect));
+                let grid = Canvas::new(Grid { days, today }).width(Length::Fixed(WEEKS as f32 * (CELL + GAP))).height(Length::Fixed(7.0 * (CELL + GAP)));
+                col.push(Text::new(format!("{} pomodoros in the last {} weeks, {} of them without a pause \u{1F525}", total, WEEKS, perfect)))
+                    .push(grid)
+                    .push(Text::new(format!("One square a day, Monday on top. Darker squares had more: {}\u{2013}{}, {}\u{2013}{}, {}\u{2013}{} and {}+", LEVELS[0], LEVELS[1] - 1, LEVELS[1], LEVELS[2] - 1, LEVELS[2], LEVELS[3] - 1, LEVELS[3])).size(14))
+            }
+            Err(err) => col.push(Text::new(format!("Could not read the session log: {}", err))),
+        };
+        Container::new(col.push(back_button)).width(Length::Fill).height(Length::Fill).into()
+    }
+}
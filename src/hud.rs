@@ -0,0 +1,36 @@
+use crate::{scaled, Pomodoro, PomodoroMessage, Screen, WINDOW_SIZE};
+use iced::{widget::{mouse_area, Container, Text}, window, Command, Element, Length, Size};
+
+/// Window size while the HUD is shown.
+const HUD_SIZE: Size = Size { width: 220.0, height: 80.0 };
+
+/// Shrinks the window to a borderless strip that stays on top of other windows. Sizes are
+/// multiplied by `ui_scale`, like everything drawn in the window.
+pub fn enter<Message>(scale: f32) -> Command<Message> {
+    Command::batch([
+        window::toggle_decorations(window::Id::MAIN),
+        window::resize(window::Id::MAIN, scaled(HUD_SIZE, scale)),
+        window::change_level(window::Id::MAIN, window::Level::AlwaysOnTop),
+    ])
+}
+
+/// Undoes `enter`.
+pub fn leave<Message>(scale: f32) -> Command<Message> {
+    Command::batch([
+        window::toggle_decorations(window::Id::MAIN),
+        window::resize(window::Id::MAIN, scaled(WINDOW_SIZE, scale)),
+        window::change_level(window::Id::MAIN, window::Level::Normal),
+    ])
+}
+
+impl Pomodoro {
+    /// Just the countdown. Dragging moves the window, right-clicking brings the full view back.
+    pub(crate) fn hud_view(&self) -> Element<'_, PomodoroMessage> {
+        let countdown = match self.fuzzy_time() {
+            Some(label) => Text::new(label).size(20),
+            None => Text::new(self.countdown()).font(self.timer_font).size(48),
+        };
+        let container = Container::new(countdown).width(Length::Fill).height(Length::Fill).center_x().center_y();
+        mouse_area(container).on_press(PomodoroMessage::DragWindow).on_right_press(PomodoroMessage::ShowScreen(Screen::Timer)).into()
+    }
+}
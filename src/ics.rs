@@ -0,0 +1,72 @@
+use crate::session_log::SessionRecord;
+use chrono::{DateTime, TimeZone, Utc};
+
+/// Builds an iCalendar file with one event per session, titled with the session's note or,
+/// without one, its mode. No sessions still makes a valid, empty calendar.
+pub fn calendar(records: &[SessionRecord], stamp: DateTime<Utc>) -> String {
+    let mut ics = String::new();
+    line(&mut ics, "BEGIN:VCALENDAR");
+    line(&mut ics, "VERSION:2.0");
+    line(&mut ics, "PRODID:-//rusty-pomodoro//sessions//EN");
+    for record in records {
+        let summary = if record.note.is_empty() { record.mode.label() } else { &record.note };
+        line(&mut ics, "BEGIN:VEVENT");
+        line(&mut ics, &format!("UID:{}-{}@rusty-pomodoro", utc(&record.started_at), record.mode.key()));
+        line(&mut ics, &format!("DTSTAMP:{}", utc(&stamp)));
+        line(&mut ics, &format!("DTSTART:{}", utc(&record.started_at)));
+        line(&mut ics, &format!("DTEND:{}", utc(&record.ended_at)));
+        line(&mut ics, &format!("SUMMARY:{}", escape(summary)));
+        line(&mut ics, "END:VEVENT");
+    }
+    line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+/// Longest a line may be, in bytes without its CRLF. Longer ones are folded.
+const MAX_LINE: usize = 75;
+
+/// iCalendar lines end in CRLF. Long ones go on over further lines that start with a space,
+/// broken between characters so none is split.
+fn line(ics: &mut String, text: &str) {
+    let mut written = 0;
+    for c in text.chars() {
+        if written + c.len_utf8() > MAX_LINE {
+            ics.push_str("\r\n ");
+            written = 1;
+        }
+        ics.push(c);
+        written += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn utc<Tz: TimeZone>(time: &DateTime<Tz>) -> String {
+    time.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escapes the characters that are special in iCalendar text values.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{session_log::RecordKind, Mode};
+    use chrono::Local;
+    use std::time::Duration;
+
+    #[test]
+    fn long_summaries_are_folded_without_splitting_characters() {
+        let now = Local::now();
+        // The tomato would straddle the 75th byte
+        let note = format!("{}\u{1F345}, then more", "a".repeat(66));
+        let record = SessionRecord { started_at: now, ended_at: now, mode: Mode::Work, planned: Duration::ZERO, actual: Duration::ZERO, completed: true, kind: RecordKind::Session, note: note.clone(), paused: false };
+        let ics = calendar(&[record], Utc::now());
+        let lines: Vec<&str> = ics.split("\r\n").collect();
+        assert!(lines.iter().all(|line| line.len() <= MAX_LINE));
+        let start = lines.iter().position(|line| line.starts_with("SUMMARY:")).unwrap();
+        assert_eq!((lines[start].len(), lines[start + 1].chars().nth(1), lines[start + 2]), (74, Some('\u{1F345}'), "END:VEVENT"));
+        assert_eq!(format!("{}{}", lines[start], &lines[start + 1][1..]), format!("SUMMARY:{}", escape(&note)));
+    }
+}
@@ -0,0 +1,189 @@
+//! Brings sessions over from other Pomodoro apps, from a CSV export with one session per row:
+//! `date,mode,duration`. The date is RFC 3339, "2024-03-01 09:30" in local time, or a bare day,
+//! in which case the day's sessions are laid end to end from 09:00. The mode is a mode key or
+//! "pomodoro", "focus", "break", "short" or "long", and the duration is minutes, like "25" or
+//! "25.5", or "mm:ss" and "h:mm:ss". A header row is skipped.
+use crate::{config::MAX_DURATION, daily::Daily, session_log::{self, RecordKind, Rotation, Rounding, RoundingTarget, SessionRecord}, Mode};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use std::{collections::{HashMap, HashSet}, fmt, fs, io, path::Path, time::Duration};
+
+/// What an import did, for reporting back.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Report {
+    pub imported: usize,
+    /// Rows already in the history, from an earlier import or because they were logged here.
+    pub duplicates: usize,
+    /// Rows that couldn't be read.
+    pub invalid: usize,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.imported {
+            1 => write!(f, "Imported 1 session")?,
+            n => write!(f, "Imported {} sessions", n)?,
+        }
+        if self.duplicates > 0 {
+            write!(f, ", skipped {} already in the history", self.duplicates)?;
+        }
+        match self.invalid {
+            0 => {}
+            1 => write!(f, ", ignored 1 invalid row")?,
+            n => write!(f, ", ignored {} invalid rows", n)?,
+        }
+        Ok(())
+    }
+}
+
+/// Adds the sessions in `source` to the log in `data_dir`, then counts the ones from today
+/// towards `daily` and rebuilds its streak from the whole history. Saving `daily` is up to the
+/// caller.
+pub fn import_history(data_dir: &Path, source: &Path, daily: &mut Daily) -> io::Result<Report> {
+    let (parsed, invalid) = parse(&fs::read_to_string(source)?);
+    let existing = session_log::read_all(data_dir)?;
+    let (records, duplicates) = new_records(&existing, parsed);
+    // Rows go in as they were exported, so neither rounding nor rotation applies
+    let rounding = Rounding { step: Duration::ZERO, target: RoundingTarget::Both };
+    let rotation = Rotation { max_bytes: 0, max_age: Duration::ZERO, keep: Duration::ZERO };
+    for record in &records {
+        session_log::append(data_dir, record, rounding, rotation)?;
+    }
+
+    daily.roll_over();
+    for record in records.iter().filter(|record| record.ended_at.date_naive() == daily.date) {
+        let counters = &mut daily.counters;
+        match record.mode {
+            Mode::Work => {
+                counters.pomodoros_started += 1;
+                counters.pomodoros_completed += 1;
+                counters.work_secs += record.actual.as_secs();
+            }
+            Mode::ShortBreak | Mode::LongBreak => counters.break_secs += record.actual.as_secs(),
+        }
+    }
+    let worked: HashSet<NaiveDate> = existing.iter().chain(&records)
+        .filter(|record| record.completed && record.mode == Mode::Work && record.kind == RecordKind::Session)
+        .map(|record| record.ended_at.date_naive())
+        .collect();
+    daily.recount_streak(&worked);
+    Ok(Report { imported: records.len(), duplicates, invalid })
+}
+
+/// Reads every row it can, returning the sessions along with the number of rows it couldn't.
+fn parse(contents: &str) -> (Vec<SessionRecord>, usize) {
+    let mut records = Vec::new();
+    let mut invalid = 0;
+    // Where the next session of a day given without a time begins
+    let mut next_start: HashMap<NaiveDate, DateTime<Local>> = HashMap::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = session_log::split_row(line);
+        let field = |i: usize| fields.get(i).map(|field| field.trim()).unwrap_or_default();
+        let (Some(when), Some(mode), Some(length)) = (when(field(0)), mode(field(1)), length(field(2))) else {
+            // Anything in the first row that isn't a session is taken for a header
+            if i > 0 {
+                invalid += 1;
+            }
+            continue;
+        };
+        let started_at = match when {
+            When::At(time) => time,
+            When::Day(day) => {
+                let Some(start) = next_start.get(&day).copied().or_else(|| Local.from_local_datetime(&day.and_time(NaiveTime::from_hms_opt(9, 0, 0).expect("09:00"))).earliest()) else {
+                    invalid += 1;
+                    continue;
+                };
+                start
+            }
+        };
+        let ended_at = started_at + chrono::Duration::from_std(length).expect("a length within a day");
+        if let When::Day(day) = when {
+            next_start.insert(day, ended_at);
+        }
+        records.push(SessionRecord { started_at, ended_at, mode, planned: length, actual: length, completed: true, kind: RecordKind::Session, note: String::from("imported"), paused: false });
+    }
+    (records, invalid)
+}
+
+/// The records not in `existing` or repeated within `parsed`, with the number left out. Two
+/// records are the same session when they are of the same mode and start in the same minute.
+fn new_records(existing: &[SessionRecord], parsed: Vec<SessionRecord>) -> (Vec<SessionRecord>, usize) {
+    let key = |record: &SessionRecord| (record.started_at.timestamp() / 60, record.mode.key());
+    let mut seen: HashSet<_> = existing.iter().map(key).collect();
+    let total = parsed.len();
+    let records: Vec<_> = parsed.into_iter().filter(|record| seen.insert(key(record))).collect();
+    let duplicates = total - records.len();
+    (records, duplicates)
+}
+
+enum When {
+    At(DateTime<Local>),
+    Day(NaiveDate),
+}
+
+fn when(text: &str) -> Option<When> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(When::At(time.with_timezone(&Local)));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(text, format) {
+            return Local.from_local_datetime(&time).earliest().map(When::At);
+        }
+    }
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").ok().map(When::Day)
+}
+
+fn mode(text: &str) -> Option<Mode> {
+    match text.to_lowercase().replace(' ', "_").as_str() {
+        "pomodoro" | "focus" => Some(Mode::Work),
+        "break" | "short" => Some(Mode::ShortBreak),
+        "long" => Some(Mode::LongBreak),
+        key => Mode::from_key(key),
+    }
+}
+
+/// Minutes, possibly with a fraction, or "mm:ss" or "h:mm:ss". Zero and anything over
+/// `MAX_DURATION` are refused.
+fn length(text: &str) -> Option<Duration> {
+    let length = if text.contains(':') {
+        let parts: Vec<u64> = text.split(':').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+        match parts[..] {
+            [minutes, seconds] if seconds < 60 => Duration::from_secs(minutes.checked_mul(60)?.checked_add(seconds)?),
+            [hours, minutes, seconds] if minutes < 60 && seconds < 60 => Duration::from_secs(hours.checked_mul(3600)?.checked_add(minutes * 60 + seconds)?),
+            _ => return None,
+        }
+    } else {
+        let minutes: f64 = text.parse().ok().filter(|minutes: &f64| minutes.is_finite() && *minutes >= 0.0)?;
+        Duration::from_secs((minutes * 60.0).round() as u64)
+    };
+    (!length.is_zero() && length <= MAX_DURATION).then_some(length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_are_read_leniently_and_bad_ones_counted() {
+        let (records, invalid) = parse("date,mode,duration\n2024-03-01 09:30,pomodoro,25\n2024-03-01,Short Break,5:00\n2024-03-01,work,0:25:00\nyesterday,work,25\n2024-03-02,work,-3\n2024-03-02,work,18446744073709551615:59\n2024-03-02,work,5124095576030432:00:00\n");
+        assert_eq!(invalid, 4);
+        assert_eq!(records.len(), 3);
+        assert_eq!((records[0].mode, records[0].actual), (Mode::Work, Duration::from_secs(25 * 60)));
+        assert_eq!(records[0].started_at.format("%Y-%m-%d %H:%M").to_string(), "2024-03-01 09:30");
+        // Sessions on a bare day follow one another from 09:00
+        assert_eq!(records[1].mode, Mode::ShortBreak);
+        assert_eq!(records[1].started_at.format("%H:%M").to_string(), "09:00");
+        assert_eq!(records[2].started_at.format("%H:%M").to_string(), "09:05");
+    }
+
+    #[test]
+    fn importing_the_same_export_twice_adds_nothing_the_second_time() {
+        let export = "2024-03-01,work,25\n2024-03-01,short_break,5\n2024-03-01,work,25\n";
+        let (first, duplicates) = new_records(&[], parse(export).0);
+        assert_eq!((first.len(), duplicates), (3, 0));
+        let (second, duplicates) = new_records(&first, parse(export).0);
+        assert_eq!((second.len(), duplicates), (0, 3));
+    }
+}
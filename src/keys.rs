@@ -0,0 +1,159 @@
+use crate::{config::Keybindings, Mode, PomodoroMessage, Screen};
+use iced::keyboard::{self, Modifiers};
+
+/// A key and the modifiers held with it, parsed from a binding like "r", "ctrl+p" or "shift+Tab".
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyCombo {
+    key: String,
+    /// Ctrl, or Cmd on macOS.
+    command: bool,
+    alt: bool,
+    shift: bool,
+}
+
+impl KeyCombo {
+    /// Parses a binding. Returns `None` for unknown modifiers or a missing key.
+    pub fn parse(binding: &str) -> Option<KeyCombo> {
+        let binding = binding.trim();
+        // A trailing "+" is the plus key itself, as in "ctrl++"
+        let (modifiers, key) = match binding.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None if binding == "+" => ("", "+"),
+            None => binding.rsplit_once('+').unwrap_or(("", binding)),
+        };
+        if key.is_empty() {
+            return None;
+        }
+        let mut combo = KeyCombo { key: key.to_ascii_lowercase(), command: false, alt: false, shift: false };
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" | "cmd" | "command" => combo.command = true,
+                "alt" | "option" => combo.alt = true,
+                "shift" => combo.shift = true,
+                _ => return None,
+            }
+        }
+        Some(combo)
+    }
+
+    /// Whether a key press is this combo. Shift is only compared for named keys unless the
+    /// binding asks for it, since it already changes which character a key types.
+    pub fn matches(&self, key: &keyboard::Key, modifiers: Modifiers) -> bool {
+        let (name_matches, named) = match key.as_ref() {
+            keyboard::Key::Character(c) => (c.eq_ignore_ascii_case(&self.key), false),
+            keyboard::Key::Named(named) => (format!("{:?}", named).eq_ignore_ascii_case(&self.key), true),
+            keyboard::Key::Unidentified => (false, false),
+        };
+        let shift_matches = modifiers.shift() == self.shift || (!named && !self.shift);
+        name_matches && modifiers.command() == self.command && modifiers.alt() == self.alt && shift_matches
+    }
+
+    /// Whether some key press matches both combos. A character bound without Shift also
+    /// matches it typed with Shift, so "r" and "shift+R" overlap.
+    fn overlaps(&self, other: &KeyCombo) -> bool {
+        let character = self.key.chars().count() == 1;
+        self.key == other.key && self.command == other.command && self.alt == other.alt && (self.shift == other.shift || character)
+    }
+}
+
+/// Whether a key press types into a text field rather than being a shortcut: a character,
+/// space or editing key without Ctrl or Alt.
+pub fn types_text(key: &keyboard::Key, modifiers: Modifiers) -> bool {
+    use keyboard::key::Named;
+    let typing = match key.as_ref() {
+        keyboard::Key::Character(_) => true,
+        keyboard::Key::Named(named) => matches!(named, Named::Space | Named::Tab | Named::Enter | Named::Backspace | Named::Delete),
+        keyboard::Key::Unidentified => false,
+    };
+    typing && !modifiers.command() && !modifiers.alt()
+}
+
+/// The parsed `keybindings`, looked up on every key press that no widget handled.
+#[derive(Debug, Default)]
+pub struct Keymap {
+    bindings: Vec<(KeyCombo, PomodoroMessage)>,
+}
+
+impl Keymap {
+    /// Builds the keymap, along with a description of any bindings that couldn't be parsed or
+    /// that share a key with an earlier one. Those are left out and the rest still work.
+    pub fn new(keybindings: &Keybindings, panic_key: &str) -> (Keymap, Option<String>) {
+        let actions = [
+            ("start_pause", &keybindings.start_pause, PomodoroMessage::StartOrPause),
+            ("reset", &keybindings.reset, PomodoroMessage::Reset),
+            ("skip", &keybindings.skip, PomodoroMessage::Skip),
+            ("work", &keybindings.work, PomodoroMessage::SwitchMode(Mode::Work)),
+            ("short_break", &keybindings.short_break, PomodoroMessage::SwitchMode(Mode::ShortBreak)),
+            ("long_break", &keybindings.long_break, PomodoroMessage::SwitchMode(Mode::LongBreak)),
+            ("next_mode", &keybindings.next_mode, PomodoroMessage::CycleMode(true)),
+            ("previous_mode", &keybindings.previous_mode, PomodoroMessage::CycleMode(false)),
+            ("hud", &keybindings.hud, PomodoroMessage::ToggleHud),
+            ("palette", &keybindings.palette, PomodoroMessage::OpenPalette),
+            ("settings", &keybindings.settings, PomodoroMessage::ShowScreen(Screen::Settings)),
+            ("debug", &keybindings.debug, PomodoroMessage::ToggleDebug),
+        ];
+        let panic_key = panic_key.to_string();
+        let mut keymap = Keymap::default();
+        let mut names: Vec<&str> = Vec::new();
+        let mut problems = Vec::new();
+        for (name, binding, message) in actions.into_iter().chain([("panic_key", &panic_key, PomodoroMessage::PanicPause)]) {
+            if binding.trim().is_empty() {
+                continue;
+            }
+            let Some(combo) = KeyCombo::parse(binding) else {
+                problems.push(format!("\"{}\" for {} isn't a key", binding, name));
+                continue;
+            };
+            if let Some(i) = keymap.bindings.iter().position(|(bound, _)| bound.overlaps(&combo)) {
+                problems.push(format!("\"{}\" is bound to both {} and {}", binding, names[i], name));
+                continue;
+            }
+            keymap.bindings.push((combo, message));
+            names.push(name);
+        }
+        let problems = (!problems.is_empty()).then(|| format!("Key bindings: {}", problems.join(", ")));
+        (keymap, problems)
+    }
+
+    pub fn lookup(&self, key: &keyboard::Key, modifiers: Modifiers) -> Option<PomodoroMessage> {
+        self.bindings.iter().find(|(combo, _)| combo.matches(key, modifiers)).map(|(_, message)| message.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combo(key: &str, command: bool, alt: bool, shift: bool) -> KeyCombo {
+        KeyCombo { key: key.to_string(), command, alt, shift }
+    }
+
+    #[test]
+    fn bindings_parse_into_a_key_and_its_modifiers() {
+        assert_eq!(KeyCombo::parse("r"), Some(combo("r", false, false, false)));
+        assert_eq!(KeyCombo::parse(" Ctrl+P "), Some(combo("p", true, false, false)));
+        assert_eq!(KeyCombo::parse("cmd+option+shift+Tab"), Some(combo("tab", true, true, true)));
+        assert_eq!(KeyCombo::parse("ctrl++"), Some(combo("+", true, false, false)));
+        assert_eq!(KeyCombo::parse("+"), Some(combo("+", false, false, false)));
+        for invalid in ["", "ctrl+", "hyper+x", "ctrl+meta+p"] {
+            assert_eq!(KeyCombo::parse(invalid), None, "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn overlapping_bindings_are_reported_and_left_out() {
+        let (keymap, problems) = Keymap::new(&Keybindings::default(), "F9");
+        assert_eq!(problems, None);
+        assert_eq!(keymap.bindings.len(), 9);
+
+        for (reset, hud) in [("r", "R"), ("r", "shift+r"), ("ctrl+Tab", "Ctrl+tab")] {
+            let keybindings = Keybindings { reset: reset.to_string(), hud: hud.to_string(), ..Keybindings::default() };
+            let (keymap, problems) = Keymap::new(&keybindings, "F9");
+            assert_eq!(problems, Some(format!("Key bindings: \"{}\" is bound to both reset and hud", hud)));
+            assert_eq!(keymap.lookup(&keyboard::Key::Character("r".into()), Modifiers::SHIFT).is_some(), reset == "r");
+        }
+        // Shift is part of named keys, as with the default Tab and Shift+Tab
+        let keybindings = Keybindings { reset: String::from("F2"), hud: String::from("shift+F2"), ..Keybindings::default() };
+        assert_eq!(Keymap::new(&keybindings, "F9").1, None);
+    }
+}
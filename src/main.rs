@@ -1,152 +1,3223 @@
-use iced::{Alignment, Element, Length, Subscription, Command, Application, time, Settings, Theme, executor, widget::{Row, Column, Button, Container, Text}};
-use std::time::{Duration, Instant};
+use iced::{Alignment, Color, Element, Font, Length, Subscription, Command, Application, time, Settings, Theme, executor, event, keyboard, window, widget::{canvas, text_input, Row, Column, Button, Canvas, Checkbox, Container, PickList, Slider, Text, TextInput}};
+use chrono::{DateTime, Local, NaiveDate};
+use config::{CompletionBehavior, Config, ResetBehavior, SkippedBreakTime};
+use daily::Daily;
+use drift::Drift;
+use ring::SecondsRing;
+use overrides::DurationOverrides;
+use persistence::Persistence;
+use rng::Rng;
+use tasks::Tasks;
+use technique::{Durations, Technique};
+use template::{Template, Variable};
+use serde::{Deserialize, Serialize};
+use session_log::{RecordKind, Rotation, Rounding, SessionRecord};
+use snapshot::{SavedState, Snapshot};
+use sound::SoundTheme;
+use std::{collections::HashMap, path::PathBuf, sync::mpsc, thread, time::{Duration, Instant}};
+
+mod active_window;
+mod attention;
+mod config;
+#[cfg(feature = "http-control")]
+mod control;
+mod daily;
+mod drift;
+mod focus;
+mod fonts;
+mod haptics;
+mod heatmap;
+mod hud;
+mod ics;
+mod import;
+mod keys;
+mod notify;
+mod overrides;
+mod palette;
+#[cfg(all(feature = "panel-dbus", target_os = "linux"))]
+mod panel;
+mod persistence;
+mod ring;
+mod routine;
+mod rng;
+mod screen_lock;
+mod session_log;
+mod settings;
+mod signals;
+mod snapshot;
+mod speech;
+mod sound;
+mod tasks;
+mod technique;
+mod template;
+mod theme;
+mod tomatoes;
+mod welcome;
 
 fn main() -> iced::Result {
+    let persistence = Persistence::resolve();
+    if std::env::args().nth(1).as_deref() == Some("--import-history") {
+        std::process::exit(import_from_args(&persistence));
+    }
+    let _in_use = persistence.data_dir().and_then(|data_dir| persistence::InUse::mark(data_dir).map_err(|err| eprintln!("Could not mark the data directory as in use: {}", err)).ok());
+    let mut config = Config::load(persistence.config_file());
+    config.overrides = DurationOverrides::from_args(std::env::args().skip(1)).or(DurationOverrides::from_env());
     Pomodoro::run(Settings {
         window: iced::window::Settings {
-            size: iced::Size { width: 640.0, height: 360.0 },
+            size: scaled(WINDOW_SIZE, config.ui_scale),
+            // iced only exposes the primary monitor before the window exists, so that is the one
+            // the window is centered on. Without monitor info this falls back to the default.
+            // Neither does it list monitors or give the cursor's place on screen afterwards, so
+            // there is nothing to pick another monitor by for a later `window::move_to`.
+            position: if config.center_on_launch { window::Position::Centered } else { window::Position::Default },
             resizable: false,
+            // So `opacity` can be changed from settings without a restart
+            transparent: true,
+            // Closing goes through `PomodoroMessage::CloseRequested` so the focus lock can step in
+            exit_on_close_request: false,
             ..iced::window::Settings::default()
         },
-        ..Settings::default()
+        ..Settings::with_flags(Flags {
+            config,
+            persistence,
+            #[cfg(all(feature = "panel-dbus", target_os = "linux"))]
+            panel: panel::Publisher::start(),
+        })
     })
 }
 
+/// `--import-history <path>`: merges another app's export into the history and says how it went.
+/// Returns the exit code.
+fn import_from_args(persistence: &Persistence) -> i32 {
+    let Some(source) = std::env::args().nth(2) else {
+        eprintln!("Usage: rusty-pomodoro --import-history <path to a date,mode,duration CSV>");
+        return 2;
+    };
+    let Some(data_dir) = persistence.data_dir() else {
+        eprintln!("No data directory was found to import into");
+        return 1;
+    };
+    // The running app keeps today's totals in memory and would write over what's imported here
+    if let Some(pid) = persistence::in_use(data_dir) {
+        eprintln!("Rusty Pomodoro is running (process {}), so nothing was imported. Quit it first, or import from its settings.", pid);
+        eprintln!("If it isn't running, delete {}", data_dir.join("running.pid").display());
+        return 1;
+    }
+    let mut daily = Daily::load(data_dir);
+    match import::import_history(data_dir, std::path::Path::new(&source), &mut daily).and_then(|report| daily.save(data_dir).map(|()| report)) {
+        Ok(report) => {
+            println!("{}", report);
+            0
+        }
+        Err(err) => {
+            eprintln!("Could not import {}: {}", source, err);
+            1
+        }
+    }
+}
+
+struct Flags {
+    config: Config,
+    /// Where settings and state are persisted between runs.
+    persistence: Persistence,
+    /// Started in `main`, so that only the app itself shows up on the bus.
+    #[cfg(all(feature = "panel-dbus", target_os = "linux"))]
+    panel: panel::Publisher,
+}
+
 struct Pomodoro {
+    config: Config,
+    persistence: Persistence,
+    screen: Screen,
+    technique: Technique,
+    durations: Durations,
     state: State,
     mode: Mode,
     timer: Duration,
+    /// Full length of the current session, what `timer` is refilled to.
+    session_total: Duration,
     last_tick: Instant,
+    rng: Rng,
+    /// Index into `config.break_tips` of the tip picked for the latest break.
+    tip: Option<usize>,
+    /// Set when `tip` was picked early, for the notification offering the break, so that
+    /// switching to the break shows the same one.
+    tip_ready: bool,
+    drift: Drift,
+    show_debug: bool,
+    completed_work_sessions: u32,
+    /// Set by a first Reset press while double-press reset is on.
+    reset_pressed_at: Option<Instant>,
+    focused: bool,
+    attention_requested: bool,
+    /// Time spent away since a restored session was paused, until the user decides whether it counts.
+    catch_up: Option<Duration>,
+    /// Time counted past zero while in `State::Overtime`.
+    overtime: Duration,
+    /// The focus lock caught a close request and is waiting for the user to confirm it.
+    confirm_quit: bool,
+    /// Wall-clock start of the session in progress, cleared once it has been logged.
+    session_started_at: Option<DateTime<Local>>,
+    daily: Daily,
+    /// Set once the shutdown work has run, so it never runs twice.
+    shut_down: bool,
+    /// Drawing of the seconds ring, redrawn only when the displayed second changes.
+    ring_cache: canvas::Cache,
+    /// The break ratio banner is showing.
+    break_warning: bool,
+    last_break_warning: Option<Instant>,
+    timer_font: Font,
+    /// The current session is a short test run; it is tagged in the log and kept out of the counters.
+    test_session: bool,
+    /// The current session is the extra time asked for from a completion notification; it
+    /// adds to the time worked but isn't counted as another session.
+    extending: bool,
+    /// Counts completions, so a notification's buttons only act on the one they were sent for.
+    completions: u64,
+    /// Pause was pressed at some point during the current session.
+    paused_this_session: bool,
+    /// The last work session went by without a pause; shown until the next work session starts.
+    perfect_badge: bool,
+    /// When the last session stopped at 00:00, until the user picks a mode or starts again.
+    stopped_at: Option<Instant>,
+    /// Time left on skipped breaks, added to the next break when `skipped_break_time` is "bank".
+    banked_break: Duration,
+    /// Break time given up by the last skip, shown until the next session starts.
+    forfeited_break: Option<Duration>,
+    /// A finished work session held back from the log until its note is written or skipped.
+    pending_note: Option<SessionRecord>,
+    note_input: String,
+    /// Time left on the micro-break being shown, if one is.
+    micro_break: Option<Duration>,
+    /// Micro-breaks already scheduled in the current work session.
+    micro_breaks_taken: u32,
+    /// Outcome of the last calendar export, shown on the settings screen.
+    export_status: Option<String>,
+    /// When Start was pressed for the current session, for the grace period.
+    started: Option<Instant>,
+    palette_query: String,
+    theme: Theme,
+    /// Themes for each mode with `mode_colors`, which win over `theme`.
+    mode_themes: Option<config::PerMode<Theme>>,
+    /// Set while the window is hidden by `PanicPause`, to whether it paused a running session.
+    panicked: Option<bool>,
+    /// Problem with the settings, shown as a banner until dismissed.
+    error: Option<String>,
+    /// Work sessions of a set left unfinished on an earlier day, while the user decides whether to continue it.
+    previous_set: Option<u32>,
+    /// When the mode last changed or a session started, and how long the countdown takes to fade
+    /// in from then.
+    transition: Option<(Instant, Duration)>,
+    /// Whether `focus_block_cmd` has run without the matching unblock yet.
+    focus_active: bool,
+    focus_commands: focus::Queue,
+    clicker: sound::Clicker,
+    /// Mode picked from the top row mid-session, waiting on `confirm_mode_switch`.
+    pending_switch: Option<Mode>,
+    keymap: keys::Keymap,
+    tasks: Tasks,
+    task_name_input: String,
+    task_estimate_input: String,
+    /// Name of the task the last work session finished, for the celebration until the next start.
+    finished_task: Option<String>,
+    /// What is typed over the countdown after clicking it, until Enter or Escape.
+    time_input: Option<String>,
+    /// Which `break_activities` have been ticked off this break.
+    activities_done: Vec<bool>,
+    /// Name of the work session, from `name_from_active_window`.
+    session_label: String,
+    /// Whether `warning_sound` has played this session.
+    warned: bool,
+    /// Time left when Reset stopped the session under `ResetBehavior::StopOnly`, for Start.
+    kept_time: Option<Duration>,
+    /// Completed pomodoros per day for the heatmap, read from the log when it is opened.
+    heatmap: Result<HashMap<NaiveDate, session_log::WorkDay>, String>,
+    /// Path typed on the settings screen for importing another app's history.
+    import_path: String,
+    /// Outcome of the last import, shown on the settings screen.
+    import_status: Option<String>,
+    /// When the last completion rang and notified, for `ALERT_GAP`.
+    last_alert: Option<Instant>,
+    /// The routine being worked through, if one was picked.
+    routine: Option<routine::Progress>,
+    /// Last time the user, or a program controlling the timer, did anything.
+    last_activity: Instant,
+    /// Dimmed and hardly redrawing after `sleep_after_minutes` of nothing happening.
+    asleep: bool,
+    /// Whether `auto_minimize_on_work_start` minimized the window, so the break knows to restore it.
+    auto_minimized: bool,
+    /// The newest row of the session log, while the user confirms deleting it.
+    removing: Option<SessionRecord>,
+    /// When each session counted this run began, with how long it really took: the log may hold
+    /// it rounded by `log_rounding_minutes`, and removing it takes off what was added.
+    exact_actual: Vec<(DateTime<Local>, Duration)>,
+    /// Outcome of the last removal, shown on the settings screen.
+    removal_status: Option<String>,
+    /// When a screen lock paused the session, for its gap in the log with `log_away_gaps`.
+    away_since: Option<DateTime<Local>>,
+    /// When the latest break started, until it is completed, for `min_break_seconds`.
+    break_began: Option<Instant>,
+    #[cfg(all(feature = "panel-dbus", target_os = "linux"))]
+    panel: panel::Publisher,
+}
+
+const TEST_SESSION: Duration = Duration::from_secs(5);
+
+/// How long `resume_countdown` counts down before a paused session picks up again.
+const RESUME_COUNTDOWN: Duration = Duration::from_secs(3);
+
+/// Lowest `opacity` allowed, so the window can't fade out of sight.
+const MIN_OPACITY: f32 = 0.3;
+
+/// Most that one "Use bank" takes out of the time bank.
+const BANK_WITHDRAWAL: Duration = Duration::from_secs(5 * 60);
+
+/// Length of the fade after a mode change, with `animate_transitions` on.
+const TRANSITION: Duration = Duration::from_millis(300);
+
+/// How often the window wakes while asleep from `sleep_after_minutes`, to start the day over at
+/// midnight and send the daily summary.
+const SLEEP_WAKE: Duration = Duration::from_secs(5 * 60);
+/// Length of the session the "5 more min" button on the completion notification starts.
+const MORE_TIME: Duration = Duration::from_secs(5 * 60);
+
+/// Shortest time between two completion sounds or notifications.
+const ALERT_GAP: Duration = Duration::from_secs(3);
+
+/// Length of the fade when a session starts, with `soft_start` on.
+const SOFT_START: Duration = Duration::from_secs(1);
+
+const BREAK_WARNING: &str = "You've been skipping breaks today. A short one will help you keep focus.";
+
+/// Work time needed before the break ratio is judged, so one session doesn't trigger a warning.
+const BREAK_RATIO_GRACE: Duration = Duration::from_secs(50 * 60);
+
+/// How long shutdown waits for the final writes before closing anyway.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+const WINDOW_SIZE: iced::Size = iced::Size { width: 640.0, height: 360.0 };
+
+/// A window size at `ui_scale`.
+fn scaled(size: iced::Size, scale: f32) -> iced::Size {
+    iced::Size::new(size.width * scale, size.height * scale)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Screen {
+    Timer,
+    Settings,
+    /// The countdown alone in a small always-on-top window.
+    Hud,
+    /// Shown once, on the very first launch.
+    Welcome,
+    /// Searchable list of every action, opened with Ctrl+P.
+    Palette,
+    /// Tasks with their estimates, and which one is being worked on.
+    Tasks,
+    /// Completed pomodoros per day over the last weeks, as a grid of shaded squares.
+    Heatmap,
+}
+
+/// What put the timer in `State::Paused`. Automatic resumes only undo pauses of their own kind.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PauseReason {
+    /// The Pause button, or an equivalent shortcut or remote command.
+    Manual,
+    /// The user was away; for now that means the app was closed with the session still going.
+    Idle,
+    ScreenLocked,
+    Meeting,
+}
+
+impl PauseReason {
+    /// Line shown under the countdown while paused.
+    fn message(self) -> &'static str {
+        match self {
+            PauseReason::Manual => "Paused",
+            PauseReason::Idle => "Paused \u{2014} you were away",
+            PauseReason::ScreenLocked => "Paused \u{2014} the screen was locked",
+            PauseReason::Meeting => "Paused \u{2014} in a meeting",
+        }
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum State {
     Idle,
-    Paused,
-    Running
+    Paused(PauseReason),
+    Running,
+    /// The session is over but keeps counting up until the next one is started.
+    Overtime,
+    /// The session is over and waits at 00:00 for the next one to be started.
+    Held,
+    /// The session counted down to zero and the completion prompt asks what to do next. Unlike
+    /// `Idle`, which is where Reset and mode switches leave a session that never finished.
+    Completed { finished: Mode },
+    /// Counting down to resuming a paused session, with `resume_countdown` on. The timer stays put.
+    Resuming { until: Instant },
+}
+
+impl PomodoroMessage {
+    /// Wraps the message of a button or checkbox, so pressing it gives `click_feedback`.
+    fn pressed(self) -> PomodoroMessage {
+        PomodoroMessage::Pressed(Box::new(self))
+    }
+}
+
+impl PomodoroMessage {
+    /// Whether this came from the user, or from a program controlling the timer, rather than
+    /// from one of the app's own timers. Anything that does keeps the window awake.
+    fn is_activity(&self) -> bool {
+        !matches!(
+            self,
+            PomodoroMessage::Tick | PomodoroMessage::ClockTick | PomodoroMessage::AnimationFrame | PomodoroMessage::SummaryCheck | PomodoroMessage::Heartbeat
+                | PomodoroMessage::SleepCheck | PomodoroMessage::ResetHintExpired | PomodoroMessage::IdleReset | PomodoroMessage::FontLoaded
+                | PomodoroMessage::ActiveWindowTitle(..)
+        )
+    }
+}
+
+impl State {
+    /// Name of the state for scripts and other programs watching the timer.
+    #[cfg(any(feature = "http-control", all(feature = "panel-dbus", target_os = "linux")))]
+    fn key(self) -> &'static str {
+        match self {
+            State::Idle => "idle",
+            State::Paused(_) => "paused",
+            State::Running => "running",
+            State::Overtime => "overtime",
+            State::Held => "held",
+            State::Completed { .. } => "completed",
+            State::Resuming { .. } => "resuming",
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 enum Mode {
     Work,
     LongBreak,
     ShortBreak,
 }
 
-#[derive(Debug, Clone, Copy)]
+impl Mode {
+    /// Name used in files and config, matching the serde representation.
+    fn key(self) -> &'static str {
+        match self {
+            Mode::Work => "work",
+            Mode::ShortBreak => "short_break",
+            Mode::LongBreak => "long_break",
+        }
+    }
+
+    /// Inverse of `key`.
+    fn from_key(key: &str) -> Option<Mode> {
+        [Mode::Work, Mode::ShortBreak, Mode::LongBreak].into_iter().find(|mode| mode.key() == key)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Mode::Work => "Work",
+            Mode::ShortBreak => "Short break",
+            Mode::LongBreak => "Long Break",
+        }
+    }
+
+    fn is_break(self) -> bool {
+        matches!(self, Mode::ShortBreak | Mode::LongBreak)
+    }
+}
+
+#[derive(Debug, Clone)]
 enum PomodoroMessage {
     Start,
     Pause,
+    /// Pauses with a reason other than the Pause button, e.g. a meeting picked from the palette.
+    PauseFor(PauseReason),
     Resume,
     SwitchMode(Mode),
+    /// Moves to the next (`true`) or previous mode button while idle.
+    CycleMode(bool),
     Reset,
-    Tick
+    Restart,
+    Skip,
+    /// Starts the mode that follows the current one in the cycle.
+    StartNext,
+    ToggleDebug,
+    KeyPressed(keyboard::Key, keyboard::Modifiers),
+    StartOrPause,
+    /// Whether the window was minimized when a session ended, if the platform can tell.
+    CompletedWhileMinimized(Option<bool>),
+    /// Pauses and minimizes the window at once; pressed again, restores it and resumes.
+    PanicPause,
+    ResetHintExpired,
+    IdleReset,
+    WindowFocused(bool),
+    ScreenLocked(bool),
+    CloseRequested,
+    /// Saves state and quits, skipping the focus lock.
+    Shutdown,
+    /// Answer to the focus lock prompt: `true` quits anyway.
+    ConfirmQuit(bool),
+    ConfirmSwitch(bool),
+    UseBank,
+    SetTaskName(String),
+    SetTaskEstimate(String),
+    AddTask,
+    /// Makes a task the current one, or `None` to work on nothing in particular.
+    SelectTask(Option<usize>),
+    RemoveTask(usize),
+    /// From the slider under the idle countdown: minutes for the current mode.
+    SetMinutes(u32),
+    SetSessionLabel(String),
+    /// The window that was in front when the work session started at this time, if known.
+    ActiveWindowTitle(DateTime<Local>, Option<String>),
+    /// One of `break_activities` was ticked or unticked.
+    ToggleActivity(usize, bool),
+    /// The digits were clicked while idle, to type a new length.
+    EditTime,
+    SetTimeInput(String),
+    SubmitTime,
+    /// From the completion prompt: start a session of this mode right away.
+    StartMode(Mode),
+    /// From the completion prompt: stop here, back on a fresh work session.
+    Done,
+    /// The button clicked on the notification sent for the numbered completion, or `None` if it
+    /// was dismissed.
+    NotificationAction(u64, Option<String>),
+    ShowScreen(Screen),
+    ToggleHud,
+    OpenPalette,
+    ClosePalette,
+    /// Closes the palette, or cancels typing a new length.
+    Escape,
+    SetPaletteQuery(String),
+    /// An action picked from the palette, run after the palette closes.
+    RunAction(Box<PomodoroMessage>),
+    /// A button or checkbox was pressed, which then does what the message inside says.
+    Pressed(Box<PomodoroMessage>),
+    GetStarted,
+    DragWindow,
+    SetSoundTheme(SoundTheme),
+    SetOpacity(f32),
+    SetUiScale(f32),
+    SetCustomSound(String),
+    PreviewSound(SoundTheme),
+    SaveConfig,
+    SetNote(String),
+    SaveNote,
+    SkipNote,
+    StartTestSession,
+    ExportCalendar,
+    /// Asks whether to delete the newest row of the session log.
+    RemoveLastSession,
+    SetImportPath(String),
+    /// Merges the export at `import_path` into the history.
+    ImportHistory,
+    /// Starts the routine of this name from the config.
+    StartRoutine(String),
+    /// Answer to that: `true` deletes it.
+    ConfirmRemoveLast(bool),
+    DismissBreakWarning,
+    DismissError,
+    /// A font file has been handed to iced; nothing to do.
+    FontLoaded,
+    /// Answer to the restore prompt: `true` takes the time away off the timer.
+    CatchUp(bool),
+    /// Answer to the previous day's unfinished set: `true` carries on with it.
+    ContinueSet(bool),
+    #[cfg(feature = "http-control")]
+    Control(control::Request),
+    Tick,
+    /// Redraws the clock header, or the wait left from `min_break_seconds`, while no session is ticking.
+    ClockTick,
+    /// Puts the window to sleep if `sleep_after_minutes` have gone by without anything happening.
+    SleepCheck,
+    /// The mouse moved or was clicked while the window slept.
+    Wake,
+    AnimationFrame,
+    /// Checks whether it's time for the daily summary.
+    SummaryCheck,
+    /// Once a minute while the timer isn't ticking, with `idle_heartbeat`.
+    Heartbeat,
+    ToggleFocusMode,
+    ToggleDayTotals,
 }
 
 impl Application for Pomodoro {
     type Message = PomodoroMessage;
     type Theme = Theme;
     type Executor = executor::Default;
-    type Flags = ();
+    type Flags = Flags;
 
-    fn new(_flags: ()) -> (Pomodoro, iced::Command<PomodoroMessage>) {
-        (Pomodoro { state: State::Idle, mode: Mode::Work, timer: Duration::from_secs(25 * 60), last_tick: Instant::now() }, iced::Command::none())
+    fn new(flags: Flags) -> (Pomodoro, iced::Command<PomodoroMessage>) {
+        let Flags { config, persistence, .. } = flags;
+        let technique = config.technique();
+        let mut problems: Vec<String> = persistence.warning().map(String::from).into_iter().collect();
+        let durations = match config::validate_durations(&technique.durations()) {
+            Ok(()) => technique.durations(),
+            Err(err) => {
+                problems.push(format!("{}; using 25/5/15 instead", err));
+                Technique::classic().durations()
+            }
+        };
+        let (keymap, key_error) = keys::Keymap::new(&config.keybindings, &config.panic_key);
+        problems.extend(key_error);
+        if let Err(time) = config.daily_summary_time() {
+            problems.push(format!("daily_summary_at \"{}\" isn't a time like 18:00", time));
+        }
+        for (name, routine) in config.routines.iter().filter(|(_, routine)| routine.repeat > routine::MAX_REPEAT) {
+            problems.push(format!("The routine \"{}\" repeats {} times, the most is {}, so it won't run", name, routine.repeat, routine::MAX_REPEAT));
+        }
+        if config.auto_cycle {
+            problems.push(String::from("auto_cycle is deprecated, so it was read as auto_start_breaks and auto_start_work; set those instead"));
+        }
+        if config.haptics && !haptics::SUPPORTED {
+            problems.push(String::from("haptics isn't supported on this platform, so it does nothing"));
+        }
+        if config.roulette.is_some_and(|roulette| roulette.too_long()) {
+            problems.push(format!("roulette can't go past {} minutes, so it stops there", config::MAX_DURATION.as_secs() / 60));
+        }
+        if let Some(Err(time)) = config.silent_hours.as_ref().map(|hours| hours.times()) {
+            problems.push(format!("silent_hours \"{}\" isn't a time like 22:00, so they're off", time));
+        }
+        for mode in [Mode::Work, Mode::ShortBreak, Mode::LongBreak] {
+            if let Some(Err(err)) = config.completion_messages.get(mode).map(|message| Template::parse(message)) {
+                problems.push(format!("completion_messages.{} won't be used: {}", mode.key(), err));
+            }
+        }
+        let error = (!problems.is_empty()).then(|| problems.join(". "));
+        if let Some(error) = &error {
+            eprintln!("{}", error);
+        }
+        let snapshot = persistence.data_dir().and_then(Snapshot::load);
+        let daily = persistence.data_dir().map(Daily::load).unwrap_or_default();
+        let tasks = persistence.data_dir().map(Tasks::load).unwrap_or_default();
+        let screen = if welcome::is_first_run(&persistence) { Screen::Welcome } else { Screen::Timer };
+        let (timer_font, load_font) = fonts::timer_font(&config);
+        let theme = theme::from_config(&config);
+        let mode_themes = theme::per_mode(&config);
+        let mut pomodoro = Pomodoro { config, persistence, screen, technique, durations, state: State::Idle, mode: Mode::Work, timer: durations.work, session_total: durations.work, last_tick: Instant::now(), rng: Rng::from_time(), tip: None, tip_ready: false, drift: Drift::default(), show_debug: false, completed_work_sessions: 0, reset_pressed_at: None, focused: true, attention_requested: false, catch_up: None, overtime: Duration::ZERO, confirm_quit: false, session_started_at: None, daily, shut_down: false, ring_cache: canvas::Cache::new(), break_warning: false, last_break_warning: None, timer_font, test_session: false, extending: false, completions: 0, paused_this_session: false, perfect_badge: false, stopped_at: None, banked_break: Duration::ZERO, forfeited_break: None, pending_note: None, note_input: String::new(), micro_break: None, micro_breaks_taken: 0, export_status: None, started: None, palette_query: String::new(), theme, mode_themes, panicked: None, error, previous_set: None, transition: None, focus_active: false, focus_commands: focus::Queue::default(), clicker: sound::Clicker::default(), pending_switch: None, keymap, tasks, task_name_input: String::new(), task_estimate_input: String::new(), finished_task: None, time_input: None, activities_done: Vec::new(), session_label: String::new(), warned: false, kept_time: None, away_since: None, break_began: None, removing: None, exact_actual: Vec::new(), removal_status: None, last_alert: None, routine: None, last_activity: Instant::now(), asleep: false, auto_minimized: false, heatmap: Ok(HashMap::new()), import_path: String::new(), import_status: None,
+            #[cfg(all(feature = "panel-dbus", target_os = "linux"))]
+            panel: flags.panel,
+        };
+        if let Some(snapshot) = snapshot {
+            pomodoro.restore(snapshot);
+        }
+        (pomodoro, load_font)
     }
 
     fn title(&self) -> String {
-        String::from("Rusty Pomodoro")
+        const BAR_WIDTH: usize = 10;
+        if self.state == State::Idle {
+            return String::from("Rusty Pomodoro");
+        }
+        let filled = ((self.progress() * BAR_WIDTH as f32) as usize).min(BAR_WIDTH);
+        let bar = match self.length_hidden() {
+            true => "?".repeat(BAR_WIDTH),
+            false => format!("{}{}", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled)),
+        };
+        let time = self.fuzzy_time().unwrap_or_else(|| self.countdown());
+        format!("[{}] {} {}", bar, time, self.config.label_for(self.mode))
     }
 
     fn update(&mut self, message: Self::Message) -> iced::Command<Self::Message> {
-        const WORK: Duration = Duration::from_secs(25 * 60);
-        const SHORT_BREAK: Duration = Duration::from_secs(5 * 60);
-        const LONG_BREAK: Duration = Duration::from_secs(15 * 60);
+        let shown_seconds = self.timer.as_secs();
+        let shown_mode = self.mode;
+        let shown_start = self.started;
+        let message = match message {
+            PomodoroMessage::Pressed(message) => {
+                self.feedback();
+                *message
+            }
+            message => message,
+        };
+        if message.is_activity() {
+            self.last_activity = Instant::now();
+            // The key that wakes the window up does only that
+            if std::mem::take(&mut self.asleep) && matches!(message, PomodoroMessage::KeyPressed(..) | PomodoroMessage::CycleMode(_) | PomodoroMessage::Escape) {
+                return Command::none();
+            }
+        }
+        let command = self.handle(message);
+        if !matches!(self.state, State::Idle | State::Completed { .. }) {
+            self.asleep = false;
+        }
+        if self.timer.as_secs() != shown_seconds {
+            self.ring_cache.clear();
+        }
+        self.sync_focus();
+        #[cfg(all(feature = "panel-dbus", target_os = "linux"))]
+        self.panel.publish(panel::Status { remaining_secs: self.remaining_shown().map(|left| left.as_secs()), mode: self.mode.key(), state: self.state.key() });
+        // Only the drawing is animated, the timer itself has already switched
+        if self.mode != shown_mode && self.config.animate_transitions {
+            self.transition = Some((Instant::now(), TRANSITION));
+        }
+        if self.started != shown_start && self.state == State::Running && self.config.soft_start {
+            self.transition = Some((Instant::now(), SOFT_START));
+            if self.mode == Mode::Work && !self.test_session {
+                focus::spawn(&self.config.soft_start_cmd);
+            }
+        }
+        if self.started != shown_start && self.state == State::Running {
+            return Command::batch([command, self.auto_minimize(), self.name_session()]);
+        }
+        command
+    }
+
+    fn subscription(&self) -> Subscription<Self::Message> {
+        let keys = keyboard::on_key_press(|key, modifiers| match key.as_ref() {
+            keyboard::Key::Named(keyboard::key::Named::ArrowRight) => Some(PomodoroMessage::CycleMode(true)),
+            keyboard::Key::Named(keyboard::key::Named::ArrowLeft) => Some(PomodoroMessage::CycleMode(false)),
+            // Everything else comes from `keybindings`, which are looked up in `update`
+            _ => Some(PomodoroMessage::KeyPressed(key, modifiers)),
+        });
+        let reset_hint = match self.reset_pressed_at {
+            Some(_) => time::every(Duration::from_millis(self.config.double_press_reset_ms)).map(|_| PomodoroMessage::ResetHintExpired),
+            None => Subscription::none(),
+        };
+        let idle_reset = match self.stopped_at {
+            Some(_) if self.config.idle_reset_minutes > 0 => time::every(Duration::from_secs(self.config.idle_reset_minutes * 60)).map(|_| PomodoroMessage::IdleReset),
+            _ => Subscription::none(),
+        };
+        let ticks = match self.state {
+            State::Idle | State::Held | State::Completed { .. } => Subscription::none(),
+            State::Paused(_) => Subscription::none(),
+            State::Running | State::Overtime => {
+                time::every(self.tick_interval()).map(|_| Self::Message::Tick)
+            }
+            State::Resuming { .. } => time::every(Duration::from_millis(100)).map(|_| Self::Message::Tick),
+        };
+        let heartbeat = match self.state {
+            State::Running | State::Overtime | State::Resuming { .. } => Subscription::none(),
+            // Asleep, the day still has to start over at midnight
+            _ if self.asleep => time::every(SLEEP_WAKE).map(|_| PomodoroMessage::Heartbeat),
+            _ if self.config.idle_heartbeat => time::every(Duration::from_secs(60)).map(|_| PomodoroMessage::Heartbeat),
+            _ => Subscription::none(),
+        };
+        let sleep = match self.state {
+            State::Idle | State::Completed { .. } if self.asleep => event::listen_with(|event, _status| match event {
+                iced::Event::Mouse(iced::mouse::Event::CursorMoved { .. } | iced::mouse::Event::ButtonPressed(_)) => Some(PomodoroMessage::Wake),
+                _ => None,
+            }),
+            State::Idle | State::Completed { .. } if self.config.sleep_after_minutes > 0 => time::every(Duration::from_secs(60)).map(|_| PomodoroMessage::SleepCheck),
+            _ => Subscription::none(),
+        };
+        let clock = match self.state {
+            State::Running | State::Overtime => Subscription::none(),
+            _ if self.asleep => Subscription::none(),
+            _ if self.config.show_clock || self.work_locked_for().is_some() => time::every(Duration::from_secs(1)).map(|_| PomodoroMessage::ClockTick),
+            _ => Subscription::none(),
+        };
+        let summary = match self.config.daily_summary_time() {
+            Ok(Some(_)) => time::every(if self.asleep { SLEEP_WAKE } else { Duration::from_secs(60) }).map(|_| PomodoroMessage::SummaryCheck),
+            _ => Subscription::none(),
+        };
+        let animation = match self.transition {
+            Some(_) => time::every(Duration::from_millis(16)).map(|_| PomodoroMessage::AnimationFrame),
+            None => Subscription::none(),
+        };
+        let window_events = event::listen_with(|event, _status| match event {
+            iced::Event::Window(_, window::Event::Focused) => Some(PomodoroMessage::WindowFocused(true)),
+            iced::Event::Window(_, window::Event::Unfocused) => Some(PomodoroMessage::WindowFocused(false)),
+            iced::Event::Window(_, window::Event::CloseRequested) => Some(PomodoroMessage::CloseRequested),
+            // Even while the palette's filter box has focus and would swallow the key
+            iced::Event::Keyboard(keyboard::Event::KeyPressed { key: keyboard::Key::Named(keyboard::key::Named::Escape), .. }) => Some(PomodoroMessage::Escape),
+            _ => None,
+        });
+        let ticks = match self.config.pause_on_screen_lock {
+            true => Subscription::batch([ticks, screen_lock::events()]),
+            false => ticks,
+        };
+        #[cfg(feature = "http-control")]
+        let ticks = match self.config.control_port {
+            Some(port) => Subscription::batch([ticks, control::listen(port)]),
+            None => ticks,
+        };
+        Subscription::batch([keys, window_events, signals::interrupts(), reset_hint, idle_reset, heartbeat, sleep, clock, summary, animation, ticks])
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        if self.asleep {
+            return self.asleep_view();
+        }
+        match self.screen {
+            Screen::Timer => self.timer_view(),
+            Screen::Settings => self.settings_view(),
+            Screen::Hud => self.hud_view(),
+            Screen::Welcome => self.welcome_view(),
+            Screen::Palette => self.palette_view(),
+            Screen::Tasks => self.tasks_view(),
+            Screen::Heatmap => self.heatmap_view(),
+        }
+    }
+
+    fn theme(&self) -> Theme {
+        self.current_theme().clone()
+    }
+
+    fn scale_factor(&self) -> f64 {
+        f64::from(self.config.ui_scale)
+    }
+
+    /// The theme's background, faded to `opacity`. Only shows through where the platform and
+    /// compositor support transparent windows; elsewhere the window stays opaque.
+    fn style(&self) -> iced::theme::Application {
+        let opacity = self.config.opacity.clamp(MIN_OPACITY, 1.0);
+        iced::theme::Application::custom(move |theme: &Theme| {
+            let background = theme.extended_palette().background.base;
+            iced::application::Appearance { background_color: Color { a: opacity, ..background.color }, text_color: background.text }
+        })
+    }
+}
+
+impl Pomodoro {
+    /// Applies a message. `update` wraps this to keep cached drawings in sync with the timer.
+    fn handle(&mut self, message: PomodoroMessage) -> Command<PomodoroMessage> {
         match message {
             PomodoroMessage::Start => {
-                self.timer = match self.mode {
-                    Mode::Work => WORK,
-                    Mode::ShortBreak => SHORT_BREAK,
-                    Mode::LongBreak => LONG_BREAK,
-                };
-                self.last_tick = Instant::now();
-                self.state = State::Running;
+                if self.state != State::Running || self.config.start_while_running_restarts {
+                    self.start();
+                }
                 Command::none()
             }
             PomodoroMessage::Resume => {
-                self.last_tick = Instant::now();
-                self.state = State::Running;
+                match self.state {
+                    State::Paused(_) if self.config.resume_countdown => self.state = State::Resuming { until: Instant::now() + RESUME_COUNTDOWN },
+                    _ => self.resume(),
+                }
                 Command::none()
             }
             PomodoroMessage::SwitchMode(mode) => {
-                self.state = State::Idle;
-                self.mode = mode;
-                self.timer = match self.mode {
-                    Mode::Work => WORK,
-                    Mode::ShortBreak => SHORT_BREAK,
-                    Mode::LongBreak => LONG_BREAK,
-                };
+                if self.config.confirm_mode_switch && matches!(self.state, State::Running | State::Paused(_) | State::Resuming { .. }) {
+                    self.pending_switch = Some(mode);
+                    return Command::none();
+                }
+                self.stopped_at = None;
+                self.routine = None;
+                self.switch_mode(mode);
+                Command::none()
+            }
+            PomodoroMessage::ConfirmSwitch(confirmed) => {
+                if let Some(mode) = self.pending_switch.take().filter(|_| confirmed) {
+                    self.stopped_at = None;
+                    self.routine = None;
+                    self.switch_mode(mode);
+                }
+                Command::none()
+            }
+            PomodoroMessage::CycleMode(forward) => {
+                // Only from the timer screen, so keys meant for the settings form don't change modes
+                if self.state == State::Idle && self.screen == Screen::Timer {
+                    self.routine = None;
+                    let buttons = &self.config.buttons;
+                    let next = match buttons.iter().position(|&mode| mode == self.mode) {
+                        Some(i) if forward => buttons[(i + 1) % buttons.len()],
+                        Some(i) => buttons[(i + buttons.len() - 1) % buttons.len()],
+                        None => buttons[0],
+                    };
+                    self.stopped_at = None;
+                    self.switch_mode(next);
+                }
                 Command::none()
             }
             PomodoroMessage::Pause => {
-                self.state = State::Paused;
+                self.pause(PauseReason::Manual);
+                Command::none()
+            }
+            PomodoroMessage::PauseFor(reason) => {
+                if self.state == State::Running {
+                    self.pause(reason);
+                }
                 Command::none()
             }
             PomodoroMessage::Reset => {
-                self.state = State::Idle;
-                self.timer = match self.mode {
-                    Mode::Work => WORK,
-                    Mode::ShortBreak => SHORT_BREAK,
-                    Mode::LongBreak => LONG_BREAK,
+                let window = Duration::from_millis(self.config.double_press_reset_ms);
+                let now = Instant::now();
+                if !window.is_zero() && self.reset_pressed_at.is_none_or(|at| now - at > window) {
+                    self.reset_pressed_at = Some(now);
+                    return Command::none();
+                }
+                self.reset_pressed_at = None;
+                self.reset(self.config.reset_behavior);
+                Command::none()
+            }
+            PomodoroMessage::Restart => {
+                // Begun again, a test run is still a test run and extra time still extra. The run
+                // being left is logged first, or cancelled within the grace period, so that
+                // `start_session` counts the new one as it would any other
+                let (test, extending) = (self.test_session, self.extending);
+                self.log_session(false);
+                self.test_session = test;
+                self.extending = extending;
+                self.start_session(self.session_total);
+                Command::none()
+            }
+            PomodoroMessage::Skip => {
+                self.skip();
+                Command::none()
+            }
+            PomodoroMessage::SetMinutes(minutes) => {
+                self.set_length(Duration::from_secs(u64::from(minutes) * 60));
+                Command::none()
+            }
+            PomodoroMessage::SetSessionLabel(label) => {
+                self.session_label = label;
+                Command::none()
+            }
+            PomodoroMessage::ActiveWindowTitle(started_at, title) => {
+                // Unless the session is over by now, or a name has been typed in meanwhile
+                if self.session_started_at == Some(started_at) && self.session_label.is_empty() {
+                    self.session_label = title.unwrap_or_default();
+                }
+                Command::none()
+            }
+            PomodoroMessage::ToggleActivity(i, done) => {
+                if let Some(activity) = self.activities_done.get_mut(i) {
+                    *activity = done;
+                }
+                Command::none()
+            }
+            PomodoroMessage::EditTime => {
+                if self.state != State::Idle {
+                    return Command::none();
+                }
+                self.time_input = Some(self.countdown());
+                text_input::focus(time_input_id())
+            }
+            PomodoroMessage::SetTimeInput(text) => {
+                if let Some(input) = &mut self.time_input {
+                    *input = text;
+                }
+                Command::none()
+            }
+            PomodoroMessage::SubmitTime => {
+                // Something that isn't a time leaves the box open, with the hint below it
+                if let Some(length) = self.time_input.as_deref().and_then(parse_time) {
+                    self.set_length(length);
+                    self.time_input = None;
+                }
+                Command::none()
+            }
+            PomodoroMessage::SetTaskName(name) => {
+                self.task_name_input = name;
+                Command::none()
+            }
+            PomodoroMessage::SetTaskEstimate(estimate) => {
+                self.task_estimate_input = estimate.chars().filter(char::is_ascii_digit).collect();
+                Command::none()
+            }
+            PomodoroMessage::AddTask => {
+                let name = self.task_name_input.trim();
+                let estimate = self.task_estimate_input.parse().unwrap_or(1).max(1);
+                if !name.is_empty() {
+                    self.tasks.tasks.push(tasks::Task { name: name.to_string(), estimate, completed: 0 });
+                    self.tasks.current.get_or_insert(self.tasks.tasks.len() - 1);
+                    self.task_name_input.clear();
+                    self.task_estimate_input.clear();
+                    self.save_tasks();
+                }
+                Command::none()
+            }
+            PomodoroMessage::SelectTask(index) => {
+                self.tasks.current = index.filter(|&i| i < self.tasks.tasks.len());
+                self.save_tasks();
+                Command::none()
+            }
+            PomodoroMessage::RemoveTask(index) => {
+                self.tasks.remove(index);
+                self.save_tasks();
+                Command::none()
+            }
+            PomodoroMessage::UseBank => {
+                self.use_bank();
+                Command::none()
+            }
+            PomodoroMessage::StartMode(mode) => {
+                self.switch_mode(mode);
+                self.start();
+                Command::none()
+            }
+            PomodoroMessage::Done => {
+                self.switch_mode(Mode::Work);
+                self.stopped_at = Some(Instant::now());
+                Command::none()
+            }
+            PomodoroMessage::StartNext => {
+                self.switch_mode(self.next_mode(self.mode));
+                self.start();
+                Command::none()
+            }
+            // The window may have moved on since the notification went out
+            PomodoroMessage::NotificationAction(completion, _) if completion != self.completions => Command::none(),
+            PomodoroMessage::NotificationAction(_, action) => match (action.as_deref(), self.state) {
+                (Some("start_next" | "default"), State::Completed { .. } | State::Held) => self.handle(PomodoroMessage::StartNext),
+                (Some("more_time"), State::Completed { .. } | State::Held) => {
+                    self.extending = true;
+                    self.start_session(MORE_TIME);
+                    Command::none()
+                }
+                _ => Command::none(),
+            },
+            PomodoroMessage::KeyPressed(key, modifiers) => {
+                // The other screens have fields to fill in, where a typed key is only a key
+                let typing = !matches!(self.screen, Screen::Timer | Screen::Hud) && keys::types_text(&key, modifiers);
+                match self.keymap.lookup(&key, modifiers).filter(|_| !typing) {
+                    Some(message) => {
+                        self.feedback();
+                        self.handle(message)
+                    }
+                    None => Command::none(),
+                }
+            }
+            PomodoroMessage::CompletedWhileMinimized(minimized) => match minimized {
+                Some(true) => Command::batch([window::minimize(window::Id::MAIN, false), window::gain_focus(window::Id::MAIN)]),
+                Some(false) | None => Command::none(),
+            },
+            PomodoroMessage::StartOrPause => match self.state {
+                State::Idle => self.handle(PomodoroMessage::Start),
+                State::Paused(_) => self.handle(PomodoroMessage::Resume),
+                State::Running | State::Resuming { .. } => self.handle(PomodoroMessage::Pause),
+                State::Overtime | State::Held | State::Completed { .. } => self.handle(PomodoroMessage::StartNext),
+            },
+            PomodoroMessage::PanicPause => {
+                if let Some(paused) = self.panicked.take() {
+                    if paused && matches!(self.state, State::Paused(_)) {
+                        self.resume();
+                    }
+                    return Command::batch([window::minimize(window::Id::MAIN, false), window::gain_focus(window::Id::MAIN)]);
+                }
+                let running = self.state == State::Running;
+                if running {
+                    self.pause(PauseReason::Manual);
+                }
+                self.panicked = Some(running);
+                window::minimize(window::Id::MAIN, true)
+            }
+            PomodoroMessage::ToggleDebug => {
+                self.show_debug = !self.show_debug;
+                Command::none()
+            }
+            PomodoroMessage::ToggleFocusMode => {
+                self.config.focus_mode = !self.config.focus_mode;
+                self.save_config();
+                Command::none()
+            }
+            PomodoroMessage::ToggleDayTotals => {
+                self.config.show_day_totals = !self.config.show_day_totals;
+                self.save_config();
+                Command::none()
+            }
+            PomodoroMessage::ResetHintExpired => {
+                self.reset_pressed_at = None;
+                Command::none()
+            }
+            PomodoroMessage::IdleReset => {
+                if matches!(self.state, State::Idle | State::Completed { .. }) && self.stopped_at.take().is_some() {
+                    self.switch_mode(Mode::Work);
+                }
+                Command::none()
+            }
+            PomodoroMessage::WindowFocused(focused) => {
+                self.focused = focused;
+                // A pause the user chose, or one waiting on the catch-up prompt, stays put
+                if focused && self.config.resume_on_focus && self.state == State::Paused(PauseReason::ScreenLocked) {
+                    self.resume();
+                }
+                if focused && self.attention_requested {
+                    self.attention_requested = false;
+                    return attention::clear();
+                }
+                Command::none()
+            }
+            PomodoroMessage::ScreenLocked(true) => {
+                if self.state == State::Running {
+                    self.pause(PauseReason::ScreenLocked);
+                    self.away_since = self.config.log_away_gaps.then(Local::now);
+                }
+                Command::none()
+            }
+            PomodoroMessage::ScreenLocked(false) => {
+                if let Some(since) = self.away_since.take() {
+                    self.log_gap(since, Local::now(), "screen locked");
+                }
+                if self.state == State::Paused(PauseReason::ScreenLocked) && self.config.resume_on_unlock {
+                    self.resume();
+                }
+                Command::none()
+            }
+            PomodoroMessage::CloseRequested => {
+                let in_work_session = self.mode == Mode::Work && matches!(self.state, State::Running | State::Paused(_) | State::Resuming { .. });
+                if self.config.focus_lock && in_work_session {
+                    self.confirm_quit = true;
+                    return self.show_screen(Screen::Timer);
+                }
+                self.shutdown()
+            }
+            PomodoroMessage::Shutdown => self.shutdown(),
+            PomodoroMessage::ConfirmQuit(quit) => {
+                self.confirm_quit = false;
+                if quit {
+                    return self.shutdown();
+                }
+                Command::none()
+            }
+            PomodoroMessage::ShowScreen(screen) => self.show_screen(screen),
+            PomodoroMessage::ToggleHud => self.show_screen(if self.screen == Screen::Hud { Screen::Timer } else { Screen::Hud }),
+            PomodoroMessage::OpenPalette => {
+                if matches!(self.screen, Screen::Timer | Screen::Settings) {
+                    self.palette_query.clear();
+                    return Command::batch([self.show_screen(Screen::Palette), text_input::focus(palette::input_id())]);
+                }
+                Command::none()
+            }
+            PomodoroMessage::Escape => match self.time_input.take() {
+                Some(_) => Command::none(),
+                None => self.handle(PomodoroMessage::ClosePalette),
+            },
+            PomodoroMessage::ClosePalette => match self.screen {
+                Screen::Palette => self.show_screen(Screen::Timer),
+                _ => Command::none(),
+            },
+            PomodoroMessage::SetPaletteQuery(query) => {
+                self.palette_query = query;
+                Command::none()
+            }
+            PomodoroMessage::RunAction(action) => {
+                let close = self.show_screen(Screen::Timer);
+                Command::batch([close, self.handle(*action)])
+            }
+            // Already unwrapped by `update`, which gives the feedback
+            PomodoroMessage::Pressed(message) => self.handle(*message),
+            PomodoroMessage::DragWindow => window::drag(window::Id::MAIN),
+            PomodoroMessage::GetStarted => {
+                if let Some(data_dir) = self.persistence.data_dir() {
+                    if let Err(err) = welcome::dismiss(data_dir) {
+                        eprintln!("Could not remember the welcome screen was seen: {}", err);
+                    }
+                }
+                self.show_screen(Screen::Timer)
+            }
+            PomodoroMessage::SetOpacity(opacity) => {
+                self.config.opacity = opacity.clamp(MIN_OPACITY, 1.0);
+                Command::none()
+            }
+            PomodoroMessage::SetUiScale(scale) => {
+                self.config.ui_scale = scale.clamp(config::MIN_UI_SCALE, config::MAX_UI_SCALE);
+                // The window keeps its size in pixels, so it grows with what is drawn in it
+                window::resize(window::Id::MAIN, scaled(WINDOW_SIZE, self.config.ui_scale))
+            }
+            PomodoroMessage::SetSoundTheme(theme) => {
+                self.config.sound_theme = theme;
+                self.save_config();
+                Command::none()
+            }
+            PomodoroMessage::SetCustomSound(path) => {
+                self.config.custom_sound = path;
+                Command::none()
+            }
+            PomodoroMessage::PreviewSound(theme) => {
+                sound::play(theme, &self.config.custom_sound);
+                Command::none()
+            }
+            PomodoroMessage::SaveConfig => {
+                self.save_config();
+                Command::none()
+            }
+            PomodoroMessage::SetNote(note) => {
+                self.note_input = note;
+                Command::none()
+            }
+            PomodoroMessage::SaveNote => {
+                self.write_pending_note();
+                Command::none()
+            }
+            PomodoroMessage::SkipNote => {
+                self.note_input.clear();
+                self.write_pending_note();
+                Command::none()
+            }
+            PomodoroMessage::ExportCalendar => {
+                self.export_status = Some(match self.export_calendar() {
+                    Ok(path) => format!("Saved {}", path.display()),
+                    Err(err) => format!("Could not export: {}", err),
+                });
+                Command::none()
+            }
+            PomodoroMessage::StartRoutine(name) => {
+                let steps = self.config.routines.get(&name).map(routine::Routine::expand).unwrap_or_default();
+                let Some(first) = steps.first().copied() else {
+                    self.error = Some(format!("The routine \"{}\" has no sessions to run", name));
+                    return Command::none();
                 };
+                self.stopped_at = None;
+                self.switch_mode(first.mode);
+                self.routine = Some(routine::Progress { name, steps, current: 0 });
+                self.start();
                 Command::none()
             }
-            PomodoroMessage::Tick => {
-                if let State::Running = self.state {
-                    let now = Instant::now();
-                    let delta = now - self.last_tick;
-                    self.last_tick = now;
-                    self.timer = self.timer.checked_sub(delta).unwrap_or_default();
-                    if self.timer.as_secs() == 0 {
-                        self.state = State::Idle;
+            PomodoroMessage::SetImportPath(path) => {
+                self.import_path = path;
+                Command::none()
+            }
+            PomodoroMessage::ImportHistory => {
+                self.write_pending_note();
+                let Some(data_dir) = self.persistence.data_dir() else {
+                    self.import_status = Some(String::from("Nothing is saved in this session, so there is no history to import into"));
+                    return Command::none();
+                };
+                let result = import::import_history(data_dir, std::path::Path::new(self.import_path.trim()), &mut self.daily);
+                self.import_status = Some(match result {
+                    Ok(report) => {
+                        self.save_daily();
+                        report.to_string()
                     }
+                    Err(err) => format!("Could not import: {}", err),
+                });
+                Command::none()
+            }
+            PomodoroMessage::RemoveLastSession => {
+                // A session still waiting on its note is the newest one, so it goes in first
+                self.write_pending_note();
+                let last = self.persistence.data_dir().map(session_log::last).transpose();
+                match last {
+                    Ok(Some(Some(record))) => self.removing = Some(record),
+                    Ok(_) => self.removal_status = Some(String::from("There is no session to remove")),
+                    Err(err) => self.removal_status = Some(format!("Could not read the log: {}", err)),
                 }
                 Command::none()
             }
-        }
-    }
-
-    fn subscription(&self) -> Subscription<Self::Message> {
-        match self.state {
-            State::Idle => Subscription::none(),
-            State::Paused => Subscription::none(),
-            State::Running { .. } => {
-                time::every(Duration::from_millis(10)).map(|_| Self::Message::Tick)
+            PomodoroMessage::ConfirmRemoveLast(remove) => {
+                let Some(expected) = self.removing.take().filter(|_| remove) else {
+                    return Command::none();
+                };
+                let removed = self.persistence.data_dir().map(|data_dir| session_log::remove_last(data_dir, &expected)).transpose();
+                self.removal_status = Some(match removed {
+                    Ok(Some(true)) => {
+                        self.unrecord(&expected);
+                        format!("Removed the {} session that ended at {}", self.config.label_for(expected.mode).to_lowercase(), expected.ended_at.format("%H:%M"))
+                    }
+                    Ok(_) => String::from("The log changed since the prompt came up, so nothing was removed. Press Remove last session again to see the newest session"),
+                    Err(err) => format!("Could not remove the session: {}", err),
+                });
+                Command::none()
             }
-        }
-    }
-
-    fn view(&self) -> Element<Self::Message> {
-        const HOUR: u64 = 60 * 60;
-        const MINUTE: u64 = 60;
-        let seconds = self.timer.as_secs();
-        let timer = Text::new(format!("{:0>2}:{:0>2}", (seconds % HOUR) / MINUTE, seconds % MINUTE)).size(120);
-        let timer_container = Container::new(timer).width(Length::Fill).center_x().center_y();
-        let work_button = Button::new("Work").width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::SwitchMode(Mode::Work));
-        let short_break_button = Button::new("Short break").width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::SwitchMode(Mode::ShortBreak));
-        let long_break_button = Button::new("Long Break").width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::SwitchMode(Mode::LongBreak));
-        let start_or_pause_or_resume_button = match self.state {
-            State::Idle => Button::new("Start").width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::Start),
-            State::Paused => Button::new("Resume").width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::Resume),
-            State::Running => Button::new("Pause").width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::Pause),
-        };
-        let reset_button = Button::new("Reset").width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::Reset);
-        let upper_row = Row::new().width(Length::Fill).height(Length::FillPortion(1)).spacing(2).push(work_button).push(short_break_button).push(long_break_button);
-        let middle_row = Row::new().width(Length::Fill).height(Length::FillPortion(3)).align_items(Alignment::Center).push(timer_container);
-        let lower_row = Row::new().width(Length::Fill).height(Length::FillPortion(1)).spacing(2).push(start_or_pause_or_resume_button).push(reset_button);
-        let col = Column::new().push(upper_row).push(middle_row).push(lower_row);
-        Container::new(col).width(Length::Fill).height(Length::Fill).center_x().center_y().into()
-    }
-
-    fn theme(&self) -> Theme {
-        iced::Theme::Dark
-    }
-}
\ No newline at end of file
+            PomodoroMessage::StartTestSession => {
+                self.switch_mode(Mode::Work);
+                self.test_session = true;
+                self.start_session(TEST_SESSION);
+                self.show_screen(Screen::Timer)
+            }
+            PomodoroMessage::DismissBreakWarning => {
+                self.break_warning = false;
+                Command::none()
+            }
+            PomodoroMessage::ContinueSet(carry_on) => {
+                if let Some(completed) = self.previous_set.take().filter(|_| carry_on) {
+                    self.completed_work_sessions = completed;
+                    self.save_snapshot();
+                }
+                Command::none()
+            }
+            PomodoroMessage::DismissError => {
+                self.error = None;
+                Command::none()
+            }
+            PomodoroMessage::FontLoaded => Command::none(),
+            PomodoroMessage::CatchUp(subtract) => {
+                match self.catch_up.take() {
+                    Some(away) if subtract && away >= self.timer => {
+                        self.timer = Duration::ZERO;
+                        return self.complete();
+                    }
+                    Some(away) if subtract => self.timer -= away,
+                    _ => {}
+                }
+                self.save_snapshot();
+                Command::none()
+            }
+            #[cfg(feature = "http-control")]
+            PomodoroMessage::Control(request) => {
+                self.control(request);
+                Command::none()
+            }
+            PomodoroMessage::ClockTick => Command::none(),
+            PomodoroMessage::SleepCheck => {
+                let after = Duration::from_secs(self.config.sleep_after_minutes * 60);
+                self.asleep = !after.is_zero() && self.last_activity.elapsed() >= after && matches!(self.state, State::Idle | State::Completed { .. });
+                Command::none()
+            }
+            PomodoroMessage::Wake => Command::none(),
+            PomodoroMessage::SummaryCheck => {
+                if let Ok(Some(at)) = self.config.daily_summary_time() {
+                    let now = Local::now();
+                    self.daily.roll_over_at(now);
+                    // Held back through silent hours, then sent once they end
+                    if now.time() >= at && !self.silent_hours() {
+                        if let Some(summary) = self.take_summary() {
+                            notify::send("Rusty Pomodoro", summary);
+                        }
+                    }
+                }
+                Command::none()
+            }
+            PomodoroMessage::Heartbeat => {
+                let date = self.daily.date;
+                self.daily.roll_over();
+                if self.daily.date != date {
+                    self.save_daily();
+                }
+                Command::none()
+            }
+            PomodoroMessage::AnimationFrame => {
+                self.transition = self.transition.filter(|(changed, length)| changed.elapsed() < *length);
+                Command::none()
+            }
+            PomodoroMessage::Tick => {
+                if let State::Running = self.state {
+                    let now = Instant::now();
+                    let before = self.timer;
+                    let delta = self.count_until(now);
+                    self.announce(before, self.timer);
+                    self.warn(before, self.timer);
+                    self.count_micro_break(delta);
+                    // Only at zero itself, so even a session of a second or two runs its full
+                    // length and 00:00 is on screen before it ends
+                    if self.timer.is_zero() {
+                        self.drift.pause(now);
+                        return self.complete();
+                    }
+                } else if let State::Overtime = self.state {
+                    let now = Instant::now();
+                    self.overtime += now - self.last_tick;
+                    self.last_tick = now;
+                } else if let State::Resuming { until } = self.state {
+                    if Instant::now() >= until {
+                        self.resume();
+                    }
+                }
+                Command::none()
+            }
+        }
+    }
+
+    /// All that is drawn while the window sleeps: the countdown, dimmed.
+    fn asleep_view(&self) -> Element<'_, PomodoroMessage> {
+        let dimmed = Color { a: 0.3, ..self.current_theme().palette().text };
+        let col = Column::new().spacing(10).align_items(Alignment::Center)
+            .push(Text::new(self.countdown()).font(self.timer_font).size(self.config.timer_font_size).style(dimmed))
+            .push(Text::new("Asleep. Move the mouse or press a key to wake up.").size(14).style(dimmed));
+        Container::new(col).width(Length::Fill).height(Length::Fill).center_x().center_y().into()
+    }
+
+    /// The prompt shown in place of the timer once a session ends with `completion = "ask"`.
+    fn completed_view(&self, finished: Mode) -> Element<'_, PomodoroMessage> {
+        let next = self.next_mode(finished);
+        let heading = self.completion_message(finished).unwrap_or_else(|| match finished {
+            Mode::Work => format!("{} session done! \u{1F345}", self.config.label_for(finished)),
+            Mode::ShortBreak | Mode::LongBreak => format!("{} is over", self.config.label_for(finished)),
+        });
+        let mut choices = Row::new().spacing(10)
+            .push(Button::new(Text::new(format!("Start {}", self.config.label_for(next).to_lowercase()))).on_press(PomodoroMessage::StartNext.pressed()));
+        if finished == Mode::Work {
+            choices = choices.push(Button::new(Text::new(format!("Another {} session", self.config.label_for(Mode::Work).to_lowercase()))).on_press(PomodoroMessage::StartMode(Mode::Work).pressed()));
+        }
+        choices = choices.push(Button::new("I'm done").on_press(PomodoroMessage::Done.pressed()));
+        let col = Column::new().spacing(20).align_items(Alignment::Center).push(Text::new(heading).size(32)).push(choices);
+        Container::new(col).width(Length::Fill).height(Length::Fill).center_x().center_y().into()
+    }
+
+    /// How long until the next tick. With `align_ticks` this is the time until the shown
+    /// second changes, plus a little so the tick lands just past the boundary.
+    fn tick_interval(&self) -> Duration {
+        if !self.config.align_ticks {
+            return Duration::from_millis(10);
+        }
+        let until_next_second = match self.state {
+            State::Overtime => Duration::from_secs(1) - Duration::from_nanos(self.overtime.subsec_nanos().into()),
+            _ => Duration::from_nanos(self.timer.subsec_nanos().into()),
+        };
+        until_next_second.max(Duration::from_millis(1)) + Duration::from_millis(2)
+    }
+
+    /// The time left in words with `fuzzy_time`, while a session is underway. Before it starts,
+    /// and in overtime, the exact countdown is shown either way.
+    fn fuzzy_time(&self) -> Option<String> {
+        let underway = matches!(self.state, State::Running | State::Paused(_) | State::Resuming { .. });
+        (self.config.fuzzy_time && underway && !self.length_hidden()).then(|| fuzzy_label(self.timer))
+    }
+
+    /// The countdown as shown on screen, counting up with a "+" in overtime.
+    fn countdown(&self) -> String {
+        const MINUTE: u64 = 60;
+        if self.length_hidden() {
+            return String::from("??:??");
+        }
+        let (sign, seconds) = match self.state {
+            State::Overtime => ("+", self.overtime.as_secs()),
+            _ => ("", self.timer.as_secs()),
+        };
+        format!("{}{:0>2}:{:0>2}", sign, seconds / MINUTE, seconds % MINUTE)
+    }
+
+    /// With `auto_minimize_on_work_start`, gets the window out of the way for a work session that
+    /// just started, and brings it back for the break after.
+    fn auto_minimize(&mut self) -> Command<PomodoroMessage> {
+        if self.mode.is_break() {
+            if !std::mem::take(&mut self.auto_minimized) {
+                return Command::none();
+            }
+            return Command::batch([window::minimize(window::Id::MAIN, false), window::gain_focus(window::Id::MAIN)]);
+        }
+        if !self.config.auto_minimize_on_work_start || self.screen == Screen::Hud || self.test_session {
+            return Command::none();
+        }
+        self.auto_minimized = true;
+        window::minimize(window::Id::MAIN, true)
+    }
+
+    /// The theme for the current mode with `mode_colors`, otherwise the one theme.
+    fn current_theme(&self) -> &Theme {
+        self.mode_themes.as_ref().and_then(|themes| themes.get(self.mode)).unwrap_or(&self.theme)
+    }
+
+    /// Text color for the countdown, faded in after a mode change or the start of a session.
+    fn transition_color(&self) -> Color {
+        let color = self.current_theme().palette().text;
+        let Some((changed, length)) = self.transition else { return color };
+        let t = (changed.elapsed().as_secs_f32() / length.as_secs_f32()).min(1.0);
+        // Ease out, so most of the fade happens right after the switch
+        let eased = 1.0 - (1.0 - t).powi(3);
+        Color { a: color.a * (0.15 + 0.85 * eased), ..color }
+    }
+
+    fn show_screen(&mut self, screen: Screen) -> Command<PomodoroMessage> {
+        let previous = std::mem::replace(&mut self.screen, screen);
+        if previous == Screen::Settings {
+            self.save_config();
+        }
+        // The log is read once on the way in rather than on every redraw
+        if screen == Screen::Heatmap {
+            self.daily.roll_over();
+            let since = heatmap::first_day(self.daily.date);
+            self.heatmap = match self.persistence.data_dir() {
+                Some(data_dir) => session_log::completed_work_per_day(data_dir, since).map_err(|err| err.to_string()),
+                None => Ok(HashMap::new()),
+            };
+        }
+        match (previous == Screen::Hud, screen == Screen::Hud) {
+            (false, true) => hud::enter(self.config.ui_scale),
+            (true, false) => hud::leave(self.config.ui_scale),
+            _ => Command::none(),
+        }
+    }
+
+    fn timer_view(&self) -> Element<'_, PomodoroMessage> {
+        if let State::Completed { finished } = self.state {
+            return self.completed_view(finished);
+        }
+        let color = self.transition_color();
+        let timer: Element<'_, PomodoroMessage> = match &self.time_input {
+            Some(input) if self.state == State::Idle => TextInput::new("MM:SS", input)
+                .id(time_input_id())
+                .on_input(PomodoroMessage::SetTimeInput)
+                .on_submit(PomodoroMessage::SubmitTime)
+                .font(self.timer_font)
+                .size(self.config.timer_font_size)
+                .width(Length::Fixed(self.config.timer_font_size * 3.2))
+                .into(),
+            _ => {
+                let digits = match self.fuzzy_time() {
+                    Some(label) => Text::new(label).size(self.config.timer_font_size * 0.4),
+                    None => Text::new(self.countdown()).font(self.timer_font).size(self.config.timer_font_size),
+                }.style(color);
+                let editable = self.state == State::Idle && !(self.mode == Mode::Work && self.config.roulette.is_some());
+                Button::new(digits).style(iced::theme::Button::Text).padding(0).on_press_maybe(editable.then_some(PomodoroMessage::EditTime).map(PomodoroMessage::pressed)).into()
+            }
+        };
+        let percent = match self.length_hidden() {
+            true => String::new(),
+            false => format!("{:.0}%", (self.progress() * 100.0).floor()),
+        };
+        let percent = Text::new(percent).size(20).style(color);
+        let mut timer_column = Column::new().align_items(Alignment::Center).push(timer).push(percent);
+        if let Some(left) = self.cycle_left().filter(|_| self.config.show_cycle_countdown && !self.length_hidden()) {
+            let next = self.config.label_for(self.upcoming_break()).to_lowercase();
+            timer_column = timer_column.push(Text::new(format!("{} until the end of the {}", short_duration(left), next)).size(16).style(color));
+        }
+        if let Some(routine) = &self.routine {
+            let left = short_duration(self.timer + routine.after_current());
+            timer_column = timer_column.push(Text::new(format!("{}: session {} of {}, {} left", routine.name, routine.current + 1, routine.steps.len(), left)).size(16).style(color));
+        } else if self.state == State::Idle && !self.config.routines.is_empty() {
+            let names: Vec<String> = self.config.routines.keys().cloned().collect();
+            timer_column = timer_column.push(PickList::new(names, None::<String>, PomodoroMessage::StartRoutine).placeholder("Start a routine").text_size(14));
+        }
+        if self.time_input.is_some() && self.state == State::Idle {
+            timer_column = timer_column.push(Text::new("Minutes, or minutes:seconds \u{2014} Enter to set, Escape to cancel").size(16));
+        }
+        if let Some(roulette) = self.config.roulette.filter(|_| self.state == State::Idle && self.mode == Mode::Work) {
+            let (low, high) = roulette.range();
+            timer_column = timer_column.push(Text::new(format!("Roulette: {}\u{2013}{} minutes", low, high)).size(16));
+        } else if self.state == State::Idle {
+            let minutes = (self.durations.get(self.mode).as_secs() / 60).clamp(1, 90) as u32;
+            timer_column = timer_column.push(Slider::new(1..=90, minutes, PomodoroMessage::SetMinutes).width(Length::Fixed(200.0)));
+        }
+        if self.state == State::Overtime {
+            timer_column = timer_column.push(Text::new(format!("{} is over, start when ready", self.config.label_for(self.mode))).size(20));
+        }
+        if self.state == State::Held {
+            let next = self.next_mode(self.mode);
+            let label = format!("{} over \u{2014} click to start {}", self.config.label_for(self.mode), self.config.label_for(next).to_lowercase());
+            timer_column = timer_column.push(Button::new(Text::new(label).size(20)).on_press(PomodoroMessage::StartNext.pressed()));
+        }
+        let until_long_break = match self.technique.sessions_until_long_break(self.completed_work_sessions) {
+            Some(1) if self.mode == Mode::Work => Some(String::from("Next break is long")),
+            Some(1) => Some(String::from("1 session until long break")),
+            Some(sessions) => Some(format!("{} sessions until long break", sessions)),
+            None => None,
+        };
+        timer_column = timer_column.push_maybe(until_long_break.map(|text| Text::new(text).size(16)));
+        if let Some(tip) = self.tip.filter(|_| self.mode.is_break()).and_then(|i| self.config.break_tips.get(i)) {
+            timer_column = timer_column.push(Text::new(tip).size(20));
+        }
+        if self.mode.is_break() && !self.activities_done.is_empty() {
+            let activities = self.config.break_activities.iter().zip(&self.activities_done).enumerate().fold(Column::new().spacing(5), |col, (i, (activity, &done))| {
+                col.push(Checkbox::new(activity, done).on_toggle(move |done| PomodoroMessage::ToggleActivity(i, done).pressed()))
+            });
+            timer_column = timer_column.push(activities);
+        }
+        if self.config.time_bank_max_minutes > 0 && self.daily.counters.banked_secs > 0 {
+            let withdrawal = self.time_bank().min(BANK_WITHDRAWAL);
+            let can_use = self.can_use_bank();
+            let bank = Row::new().spacing(10).align_items(Alignment::Center)
+                .push(Text::new(format!("{} in the time bank", short_duration(self.time_bank()))).size(16))
+                .push(Button::new(Text::new(format!("Use {}", short_duration(withdrawal))).size(16)).on_press_maybe(can_use.then_some(PomodoroMessage::UseBank).map(PomodoroMessage::pressed)));
+            timer_column = timer_column.push(bank);
+        }
+        if !self.banked_break.is_zero() {
+            timer_column = timer_column.push(Text::new(format!("{} banked for the next break", short_duration(self.banked_break))).size(16));
+        }
+        if let Some(forfeited) = self.forfeited_break {
+            timer_column = timer_column.push(Text::new(format!("{} of break forfeited", short_duration(forfeited))).size(16));
+        }
+        if let Some(left) = self.micro_break {
+            timer_column = timer_column.push(Text::new(format!("Micro-break: look away from the screen ({})", short_duration(left + Duration::from_millis(999)))).size(20));
+        }
+        if self.pending_note.is_some() {
+            let prompt = Row::new().spacing(5).align_items(Alignment::Center)
+                .push(TextInput::new("What did you get done?", &self.note_input).on_input(PomodoroMessage::SetNote).on_submit(PomodoroMessage::SaveNote).width(Length::Fixed(300.0)))
+                .push(Button::new("Save").on_press(PomodoroMessage::SaveNote.pressed()))
+                .push(Button::new("Skip").on_press(PomodoroMessage::SkipNote.pressed()));
+            timer_column = timer_column.push(prompt);
+        }
+        if let State::Resuming { until } = self.state {
+            let seconds = until.saturating_duration_since(Instant::now()).as_secs() + 1;
+            let label = Text::new(format!("Resuming in {}\u{2026}", seconds)).size(20);
+            timer_column = timer_column.push(Button::new(label).style(iced::theme::Button::Text).on_press(PomodoroMessage::Resume.pressed()));
+        }
+        if let State::Paused(reason) = self.state {
+            timer_column = timer_column.push(Text::new(reason.message()).size(16));
+        }
+        if let Some(name) = &self.finished_task {
+            timer_column = timer_column.push(Text::new(format!("\u{1F389} Finished \"{}\"!", name)).size(20));
+        } else if let Some(task) = self.tasks.current() {
+            timer_column = timer_column.push(Text::new(format!("{} \u{2014} {} of {} done", task.name, task.completed, task.estimate)).size(16));
+        } else if self.config.name_from_active_window && self.mode == Mode::Work {
+            timer_column = timer_column.push(TextInput::new("What are you working on?", &self.session_label).on_input(PomodoroMessage::SetSessionLabel).width(Length::Fixed(300.0)));
+        }
+        if self.long_break_due() {
+            let message = match self.config.block_work_until_long_break {
+                true => format!("Time for a real break \u{2014} {} sessions since your last long break. Work can start again after one.", self.daily.counters.work_since_long_break),
+                false => format!("Time for a real break \u{2014} {} sessions since your last long break", self.daily.counters.work_since_long_break),
+            };
+            timer_column = timer_column.push(Text::new(message).size(16));
+        }
+        if let Some(left) = self.work_locked_for() {
+            timer_column = timer_column.push(Text::new(format!("Work can start in {}, give the break a little longer", short_duration(left))).size(16));
+        }
+        if self.focus_active {
+            timer_column = timer_column.push(Text::new("Focus mode on \u{2014} distractions blocked").size(16));
+        }
+        if self.perfect_badge {
+            timer_column = timer_column.push(Text::new(format!("No pauses! \u{1F525} ({} today)", self.daily.counters.perfect_sessions)).size(16));
+        }
+        if let Some(error) = &self.error {
+            let banner = Row::new().spacing(10).align_items(Alignment::Center)
+                .push(Text::new(error).size(16))
+                .push(Button::new("Dismiss").on_press(PomodoroMessage::DismissError.pressed()));
+            timer_column = timer_column.push(banner);
+        }
+        if self.break_warning {
+            let banner = Row::new().spacing(10).align_items(Alignment::Center)
+                .push(Text::new(BREAK_WARNING).size(16))
+                .push(Button::new("Dismiss").on_press(PomodoroMessage::DismissBreakWarning.pressed()));
+            timer_column = timer_column.push(banner);
+        }
+        if self.confirm_quit {
+            let prompt = Text::new("A work session is in progress. Quit anyway?").size(16);
+            let choices = Row::new().spacing(5)
+                .push(Button::new("Keep working").on_press(PomodoroMessage::ConfirmQuit(false).pressed()))
+                .push(Button::new("Quit").on_press(PomodoroMessage::ConfirmQuit(true).pressed()));
+            timer_column = timer_column.push(prompt).push(choices);
+        }
+        if let Some(mode) = self.pending_switch {
+            let prompt = Text::new(format!("Switch to {}? This ends your current {} session.", self.config.label_for(mode), self.config.label_for(self.mode).to_lowercase())).size(16);
+            let choices = Row::new().spacing(5)
+                .push(Button::new("Cancel").on_press(PomodoroMessage::ConfirmSwitch(false).pressed()))
+                .push(Button::new(Text::new(format!("Switch to {}", self.config.label_for(mode)))).on_press(PomodoroMessage::ConfirmSwitch(true).pressed()));
+            timer_column = timer_column.push(prompt).push(choices);
+        }
+        if let Some(away) = self.catch_up {
+            let minutes = away.as_secs() / 60;
+            let prompt = Text::new(format!("Paused {}h {:0>2}m ago", minutes / 60, minutes % 60)).size(16);
+            let choices = Row::new().spacing(5)
+                .push(Button::new("Resume where I left off").on_press(PomodoroMessage::CatchUp(false).pressed()))
+                .push(Button::new("Count the time away").on_press(PomodoroMessage::CatchUp(true).pressed()));
+            timer_column = timer_column.push(prompt).push(choices);
+        }
+        if let (Some(completed), Some(every)) = (self.previous_set, self.technique.sessions_before_long_break) {
+            let prompt = Text::new(format!("Last time's set stopped at {}/{}", completed % every, every)).size(16);
+            let choices = Row::new().spacing(5)
+                .push(Button::new("Continue it").on_press(PomodoroMessage::ContinueSet(true).pressed()))
+                .push(Button::new("Start fresh").on_press(PomodoroMessage::ContinueSet(false).pressed()));
+            timer_column = timer_column.push(prompt).push(choices);
+        }
+        if self.reset_pressed_at.is_some() {
+            timer_column = timer_column.push(Text::new("Press again to reset").size(16));
+        }
+        if self.show_debug {
+            timer_column = timer_column.push(Text::new(format!("drift: {:+.2}s", self.drift.seconds(Instant::now()))).size(14));
+        }
+        let seconds_ring = self.config.seconds_ring.then(|| {
+            let ring = Canvas::new(SecondsRing { seconds: self.timer.as_secs(), cache: &self.ring_cache }).width(Length::Fixed(150.0)).height(Length::Fixed(150.0));
+            Container::new(ring).padding(10)
+        });
+        let timer_container = Container::new(timer_column).width(Length::Fill).center_x().center_y();
+        let start_or_pause_or_resume_button = match self.state {
+            State::Idle => {
+                let locked = self.mode == Mode::Work && self.work_locked_for().is_some();
+                Button::new("Start").width(Length::FillPortion(1)).height(Length::Fill).on_press_maybe((!locked).then_some(PomodoroMessage::Start).map(PomodoroMessage::pressed))
+            }
+            State::Paused(_) => Button::new("Resume").width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::Resume.pressed()),
+            State::Running => Button::new("Pause").width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::Pause.pressed()),
+            State::Resuming { .. } => Button::new("Resume now").width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::Resume.pressed()),
+            State::Overtime | State::Held | State::Completed { .. } => {
+                let next = self.next_mode(self.mode);
+                Button::new(Text::new(format!("Start {}", self.config.label_for(next)))).width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::StartNext.pressed())
+            }
+        };
+        let restart_button = match self.state {
+            State::Idle => None,
+            // Start takes Restart's place, so there aren't two ways to start over side by side
+            State::Paused(_) if self.config.start_from_paused_restarts => Some(Button::new("Start").width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::Start.pressed())),
+            State::Paused(_) | State::Resuming { .. } | State::Running | State::Overtime | State::Held | State::Completed { .. } => Some(Button::new("Restart").width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::Restart.pressed())),
+        };
+        let skip_button = match self.state {
+            State::Running | State::Paused(_) | State::Resuming { .. } => Some(Button::new("Skip").width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::Skip.pressed())),
+            State::Idle | State::Overtime | State::Held | State::Completed { .. } => None,
+        };
+        let reset_button = Button::new("Reset").width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::Reset.pressed());
+        let upper_row = self.config.buttons.iter().fold(Row::new().width(Length::Fill).height(Length::FillPortion(1)).spacing(2), |row, &mode| {
+            row.push(Button::new(self.config.label_for(mode)).width(Length::FillPortion(1)).height(Length::Fill).on_press(PomodoroMessage::SwitchMode(mode).pressed()))
+        }).push(Button::new("Tasks").height(Length::Fill).on_press(PomodoroMessage::ShowScreen(Screen::Tasks).pressed()))
+            .push(Button::new("HUD").height(Length::Fill).on_press(PomodoroMessage::ShowScreen(Screen::Hud).pressed()))
+            .push(Button::new("Settings").height(Length::Fill).on_press(PomodoroMessage::ShowScreen(Screen::Settings).pressed()));
+        let pomodoro_badge = match self.daily.counters.pomodoros_started {
+            0 => None,
+            number => Some(Container::new(Text::new(format!("#{}", number)).size(24)).height(Length::Fill).padding(10)),
+        };
+        let middle_row = Row::new().width(Length::Fill).height(Length::FillPortion(3)).align_items(Alignment::Center).push_maybe(seconds_ring).push(timer_container).push_maybe(pomodoro_badge);
+        let lower_row = Row::new().width(Length::Fill).height(Length::FillPortion(1)).spacing(2).push(start_or_pause_or_resume_button).push_maybe(restart_button).push_maybe(skip_button).push(reset_button);
+        let earned = (self.config.daily_goal > 0).then(|| {
+            Container::new(tomatoes::earned(self.daily.counters.pomodoros_completed, self.config.daily_goal)).width(Length::Fill).center_x().padding(5)
+        });
+        let clock = self.config.show_clock.then(|| {
+            Container::new(Text::new(Local::now().format("%a %-d %b %Y  %H:%M").to_string()).size(14)).width(Length::Fill).center_x().padding(2)
+        });
+        let day_totals = self.config.show_day_totals.then(|| {
+            let (work, breaks) = self.day_totals();
+            let totals = format!("Work {} \u{b7} Breaks {}", daily::hours_minutes(work.as_secs()), daily::hours_minutes(breaks.as_secs()));
+            Container::new(Text::new(totals).size(14)).width(Length::Fill).center_x().padding(2)
+        });
+        let col = Column::new().push_maybe(clock).push(upper_row).push(middle_row).push_maybe(earned).push_maybe(day_totals).push(lower_row);
+        Container::new(col).width(Length::Fill).height(Length::Fill).center_x().center_y().into()
+    }
+
+    /// Time spent counting down today in work and in breaks. Today's counters only take sessions
+    /// in once they end, so the one in progress is added on top.
+    fn day_totals(&self) -> (Duration, Duration) {
+        let counters = &self.daily.counters;
+        let (mut work, mut breaks) = (Duration::from_secs(counters.work_secs), Duration::from_secs(counters.break_secs));
+        let today = self.daily.date == Local::now().date_naive();
+        if self.session_started_at.is_some() && !self.test_session && today {
+            let elapsed = self.session_total.saturating_sub(self.timer);
+            if self.mode.is_break() { breaks += elapsed } else { work += elapsed }
+        }
+        (work, breaks)
+    }
+
+    /// The mode after `finished`: the technique's pick, unless `long_break_after` calls for a long break.
+    fn next_mode(&self, finished: Mode) -> Mode {
+        self.mode_after(finished, self.completed_work_sessions, self.daily.counters.work_since_long_break)
+    }
+
+    /// `next_mode` with the given counts of work sessions in the set and since the last long break.
+    fn mode_after(&self, finished: Mode, completed_work_sessions: u32, work_since_long_break: u32) -> Mode {
+        match finished {
+            Mode::Work if self.long_break_due_at(work_since_long_break) => Mode::LongBreak,
+            _ => self.technique.next_mode(finished, completed_work_sessions),
+        }
+    }
+
+    /// The break that the current work session leads into, as if it had just been completed:
+    /// the routine's next step when one is running, as in `complete`.
+    fn upcoming_break(&self) -> Mode {
+        match self.routine.as_ref().and_then(|routine| routine.next()) {
+            Some(step) => step.mode,
+            None => self.mode_after(Mode::Work, self.completed_work_sessions + 1, self.daily.counters.work_since_long_break + 1),
+        }
+    }
+
+    /// Time left until the end of the break after this work session, if that break follows on
+    /// its own.
+    fn cycle_left(&self) -> Option<Duration> {
+        if self.mode != Mode::Work || self.test_session || !matches!(self.state, State::Idle | State::Running | State::Paused(_) | State::Resuming { .. }) {
+            return None;
+        }
+        // A routine's steps follow each other on their own
+        if let Some(step) = self.routine.as_ref().and_then(|routine| routine.next()) {
+            return Some(self.timer + step.length());
+        }
+        let next = self.upcoming_break();
+        (self.config.completion(Mode::Work, next) == CompletionBehavior::AutoNext).then(|| self.timer + self.planned(next))
+    }
+
+    /// How much longer the latest break has to go on for before work can start, with
+    /// `min_break_seconds`.
+    fn work_locked_for(&self) -> Option<Duration> {
+        let began = self.break_began?;
+        Duration::from_secs(self.config.min_break_seconds).checked_sub(began.elapsed()).filter(|left| !left.is_zero())
+    }
+
+    /// Whether today's work since the last long break has reached `long_break_after`.
+    fn long_break_due(&self) -> bool {
+        self.long_break_due_at(self.daily.counters.work_since_long_break)
+    }
+
+    fn long_break_due_at(&self, work_since_long_break: u32) -> bool {
+        self.config.long_break_after > 0 && work_since_long_break >= self.config.long_break_after
+    }
+
+    fn start(&mut self) {
+        if let Some(left) = self.kept_time.take() {
+            return self.start_session(left);
+        }
+        if let Some(step) = self.routine.as_ref().and_then(|routine| routine.steps.get(routine.current)).filter(|step| step.mode == self.mode) {
+            return self.start_session(step.length());
+        }
+        let length = match self.config.roulette {
+            Some(roulette) if self.mode == Mode::Work => {
+                let (low, high) = roulette.range();
+                let minutes = low.saturating_add(self.rng.below((high - low + 1) as usize) as u64);
+                Duration::from_secs(minutes.saturating_mul(60))
+            }
+            _ => self.planned(self.mode),
+        };
+        self.start_session(length);
+    }
+
+    /// Changes the length of the current mode from the timer screen. Only while idle, since a
+    /// running session keeps the length it started with.
+    fn set_length(&mut self, length: Duration) {
+        if let Err(err) = config::validate_length(self.mode, length) {
+            self.error = Some(err.to_string());
+            return;
+        }
+        if self.state == State::Idle {
+            self.durations.set(self.mode, length);
+            self.session_total = self.planned(self.mode);
+            self.timer = self.session_total;
+            self.kept_time = None;
+            self.save_snapshot();
+        }
+    }
+
+    /// The time left as other programs may see it: `None` while `length_hidden`.
+    #[cfg(any(feature = "http-control", all(feature = "panel-dbus", target_os = "linux")))]
+    fn remaining_shown(&self) -> Option<Duration> {
+        (!self.length_hidden()).then_some(self.timer)
+    }
+
+    /// Whether the countdown is hidden because roulette picked this session's length.
+    fn length_hidden(&self) -> bool {
+        let hide = self.config.roulette.is_some_and(|roulette| roulette.hide_length);
+        hide && self.mode == Mode::Work && !self.test_session && matches!(self.state, State::Running | State::Paused(_) | State::Resuming { .. })
+    }
+
+    fn start_session(&mut self, length: Duration) {
+        // Lengths come from the technique, roulette, routines and the timer screen alike
+        if let Err(err) = config::validate_length(self.mode, length) {
+            self.error = Some(err.to_string());
+            return;
+        }
+        if self.mode == Mode::Work && !self.test_session && !self.extending && (self.config.block_work_until_long_break && self.long_break_due() || self.work_locked_for().is_some()) {
+            return;
+        }
+        self.time_input = None;
+        self.kept_time = None;
+        // Starting over from a paused session abandons it
+        self.log_session(false);
+        self.catch_up = None;
+        self.session_started_at = Some(Local::now());
+        if self.mode == Mode::Work && !self.test_session && !self.extending {
+            self.daily.roll_over();
+            self.daily.counters.pomodoros_started += 1;
+            self.save_daily();
+        }
+        if self.mode == Mode::Work && !self.extending {
+            self.perfect_badge = false;
+            self.finished_task = None;
+            // Filled in by `name_session` once the window in front is known
+            if self.config.name_from_active_window {
+                self.session_label.clear();
+            }
+        }
+        self.paused_this_session = false;
+        self.warned = false;
+        self.previous_set = None;
+        self.stopped_at = None;
+        self.forfeited_break = None;
+        self.micro_break = None;
+        self.micro_breaks_taken = 0;
+        if self.mode.is_break() {
+            self.banked_break = Duration::ZERO;
+            self.break_began = (!self.test_session).then(Instant::now);
+            self.activities_done = vec![false; self.config.break_activities.len()];
+        }
+        self.session_total = length;
+        self.timer = self.session_total;
+        self.started = Some(Instant::now());
+        self.last_tick = Instant::now();
+        self.drift.start(self.last_tick);
+        self.state = State::Running;
+        self.save_snapshot();
+    }
+
+    fn resume(&mut self) {
+        self.last_tick = Instant::now();
+        self.drift.resume(self.last_tick);
+        self.state = State::Running;
+        self.catch_up = None;
+        self.save_snapshot();
+    }
+
+    /// Takes the time since the last tick off the timer and returns it.
+    fn count_until(&mut self, now: Instant) -> Duration {
+        let delta = now.saturating_duration_since(self.last_tick);
+        self.last_tick = now;
+        let remaining = self.timer.saturating_sub(delta);
+        self.drift.count(self.timer - remaining);
+        self.timer = remaining;
+        delta
+    }
+
+    fn pause(&mut self, reason: PauseReason) {
+        let now = Instant::now();
+        // The part of a second since the last tick counts too, so resuming picks up at the exact spot
+        if self.state == State::Running {
+            self.count_until(now);
+        }
+        self.paused_this_session = true;
+        self.drift.pause(now);
+        self.state = State::Paused(reason);
+        self.save_snapshot();
+    }
+
+    /// Stops the session and starts the set over, leaving the countdown as `behavior` says.
+    /// Today's totals are kept.
+    fn reset(&mut self, behavior: ResetBehavior) {
+        let left = if self.state == State::Overtime { Duration::ZERO } else { self.timer };
+        self.log_session(false);
+        self.completed_work_sessions = 0;
+        self.micro_break = None;
+        self.pending_switch = None;
+        self.routine = None;
+        self.stopped_at = None;
+        self.state = State::Idle;
+        self.session_total = self.planned(self.mode);
+        self.timer = match behavior {
+            ResetBehavior::RefillAndStop => self.session_total,
+            ResetBehavior::StopOnly => left,
+            ResetBehavior::Clear => Duration::ZERO,
+        };
+        self.kept_time = (behavior == ResetBehavior::StopOnly && !left.is_zero() && left < self.session_total).then_some(left);
+        self.catch_up = None;
+        self.save_snapshot();
+    }
+
+    fn switch_mode(&mut self, mode: Mode) {
+        self.log_session(false);
+        self.micro_break = None;
+        self.state = State::Idle;
+        let tip_ready = std::mem::take(&mut self.tip_ready);
+        if mode.is_break() && !tip_ready {
+            self.pick_tip();
+        }
+        self.mode = mode;
+        self.session_total = self.planned(self.mode);
+        self.timer = self.session_total;
+        self.kept_time = None;
+        self.catch_up = None;
+        self.save_snapshot();
+    }
+
+    /// Speaks the time left if the countdown went from `before` to `after` across one of the
+    /// announcement thresholds. Only the last one crossed is spoken when ticks bunch up.
+    fn announce(&self, before: Duration, after: Duration) {
+        if !self.config.announcements || self.config.sound_theme == SoundTheme::Silent || self.silent_hours() || after.is_zero() {
+            return;
+        }
+        let halfway = self.config.announce_halfway.then_some(self.session_total / 2);
+        let minutes = self.config.announce_minutes.iter().map(|&minutes| Duration::from_secs(minutes * 60));
+        let crossed = minutes.chain(halfway).filter(|&threshold| after <= threshold && threshold < before).min();
+        if let Some(threshold) = crossed {
+            speech::say(speech::remaining(threshold));
+        }
+    }
+
+    /// Plays `warning_sound` when the countdown crosses `warning_seconds`, once per session even
+    /// if time added from the bank takes it back above the threshold.
+    fn warn(&mut self, before: Duration, after: Duration) {
+        let threshold = Duration::from_secs(self.config.warning_seconds);
+        if self.warned || threshold.is_zero() || after.is_zero() || !(after <= threshold && threshold < before) {
+            return;
+        }
+        self.warned = true;
+        if self.config.sound_theme != SoundTheme::Silent && !self.silent_hours() {
+            sound::play(self.config.warning_sound, &self.config.custom_warning_sound);
+        }
+    }
+
+    /// Runs down the micro-break on screen, or brings up the next one once another
+    /// `micro_break_every_minutes` of the work session have gone by.
+    fn count_micro_break(&mut self, delta: Duration) {
+        if let Some(left) = self.micro_break {
+            self.micro_break = left.checked_sub(delta).filter(|left| !left.is_zero());
+            return;
+        }
+        let every = Duration::from_secs(self.config.micro_break_every_minutes * 60);
+        let length = Duration::from_secs(self.config.micro_break_seconds);
+        if self.mode != Mode::Work || every.is_zero() || length.is_zero() {
+            return;
+        }
+        let elapsed = self.session_total.saturating_sub(self.timer);
+        let due = (elapsed.as_secs() / every.as_secs()) as u32;
+        if due > self.micro_breaks_taken {
+            // A jump over several intervals, like after catching up, only brings up one
+            self.micro_breaks_taken = due;
+            if self.timer > length {
+                self.micro_break = Some(length);
+            }
+        }
+    }
+
+    /// Length of a fresh `mode` session, including any banked break time for breaks.
+    fn planned(&self, mode: Mode) -> Duration {
+        match mode.is_break() {
+            true => self.durations.get(mode) + self.banked_break,
+            false => self.durations.get(mode),
+        }
+    }
+
+    /// Ends the session early and moves on. A skipped work session doesn't count towards the
+    /// long break, so it is always followed by a short one.
+    fn skip(&mut self) {
+        // A routine moves on to its own next session
+        if self.advance_routine() {
+            return;
+        }
+        let left = self.timer;
+        let next = match self.mode {
+            Mode::Work => Mode::ShortBreak,
+            Mode::ShortBreak | Mode::LongBreak => {
+                match self.config.skipped_break_time {
+                    SkippedBreakTime::Forfeit => self.forfeited_break = Some(self.timer),
+                    SkippedBreakTime::Bank => self.banked_break += self.timer,
+                }
+                Mode::Work
+            }
+        };
+        self.switch_mode(next);
+        let max = Duration::from_secs(self.config.time_bank_max_minutes * 60);
+        if next.is_break() && !max.is_zero() {
+            self.daily.roll_over();
+            let banked = (self.time_bank() + left).min(max);
+            self.daily.counters.banked_secs = banked.as_secs();
+            self.save_daily();
+        }
+    }
+
+    fn time_bank(&self) -> Duration {
+        Duration::from_secs(self.daily.counters.banked_secs)
+    }
+
+    /// Whether there's a session underway for time from the bank to go onto. Before one starts,
+    /// starting it would set the length afresh and the time would be lost.
+    fn can_use_bank(&self) -> bool {
+        matches!(self.state, State::Running | State::Paused(_) | State::Resuming { .. })
+    }
+
+    /// Moves up to `BANK_WITHDRAWAL` from the time bank onto the current session.
+    fn use_bank(&mut self) {
+        if !self.can_use_bank() {
+            return;
+        }
+        self.daily.roll_over();
+        let withdrawal = self.time_bank().min(BANK_WITHDRAWAL);
+        if withdrawal.is_zero() {
+            return;
+        }
+        self.timer += withdrawal;
+        self.session_total += withdrawal;
+        self.daily.counters.banked_secs -= withdrawal.as_secs();
+        self.save_daily();
+        self.save_snapshot();
+    }
+
+    /// Called once the running session has counted down to zero.
+    fn complete(&mut self) -> Command<PomodoroMessage> {
+        let test = self.test_session;
+        // The extra time carries on the session that just completed rather than adding one
+        let counted = !test && !self.extending;
+        self.completions += 1;
+        self.pending_switch = None;
+        self.log_session(true);
+        if self.mode == Mode::Work && counted {
+            self.completed_work_sessions += 1;
+            self.daily.counters.pomodoros_completed += 1;
+            self.daily.counters.work_since_long_break += 1;
+            self.daily.extend_streak();
+            let silent = self.silent_hours();
+            if let Some(task) = self.tasks.count_session() {
+                self.finished_task = Some(task.name.clone());
+                if !silent {
+                    notify::send("Task done!", format!("Finished \"{}\" in {} pomodoros", task.name, task.completed));
+                }
+            }
+            self.save_tasks();
+            if !self.paused_this_session {
+                self.daily.counters.perfect_sessions += 1;
+                self.perfect_badge = true;
+            }
+            self.save_daily();
+        }
+        if self.mode == Mode::LongBreak && counted {
+            self.daily.counters.work_since_long_break = 0;
+            self.save_daily();
+        }
+        if self.mode.is_break() {
+            self.break_began = None;
+        }
+        // A routine decides what comes next, ahead of the completion settings
+        let in_routine = !test && self.advance_routine();
+        let mut notification = None;
+        if !in_routine {
+            let next = self.next_mode(self.mode);
+            match self.config.completion(self.mode, next) {
+                CompletionBehavior::Stop => {
+                    self.state = State::Completed { finished: self.mode };
+                    self.stopped_at = Some(Instant::now());
+                    self.save_snapshot();
+                }
+                CompletionBehavior::AutoNext => {
+                    self.switch_mode(next);
+                    self.start();
+                }
+                CompletionBehavior::Overtime => {
+                    self.state = State::Overtime;
+                    self.overtime = Duration::ZERO;
+                    self.save_snapshot();
+                }
+                CompletionBehavior::Ask => {
+                    self.state = State::Completed { finished: self.mode };
+                    self.save_snapshot();
+                    notification = Some((self.completion_summary(), String::from("What's next?")));
+                }
+                CompletionBehavior::HoldUntilDismissed => {
+                    self.state = State::Held;
+                    self.save_snapshot();
+                    let next = self.next_mode(self.mode);
+                    notification = Some((self.completion_summary(), format!("Click to start {}", self.config.label_for(next).to_lowercase())));
+                }
+            }
+            if let Some((_, body)) = notification.as_mut().filter(|_| next.is_break() && self.config.tip_in_notification) {
+                self.pick_tip();
+                self.tip_ready = true;
+                if let Some(tip) = self.tip.and_then(|i| self.config.break_tips.get(i)) {
+                    body.push_str(&format!("\n{}", tip));
+                }
+            }
+        }
+        // Back-to-back sessions only seconds long would otherwise ring and notify on every one
+        let mut clicked = Command::none();
+        if !self.silent_hours() && self.alert_due() {
+            if let Some((summary, body)) = notification {
+                let start_next = format!("Start {}", self.config.label_for(self.next_mode(self.mode)).to_lowercase());
+                let actions = vec![("start_next", start_next), ("more_time", format!("{} more min", MORE_TIME.as_secs() / 60))];
+                let completion = self.completions;
+                clicked = Command::perform(notify::send_with_actions(summary, body, actions), move |action| PomodoroMessage::NotificationAction(completion, action));
+            }
+            sound::play(self.config.sound_theme, &self.config.custom_sound);
+            if self.config.haptics {
+                haptics::buzz();
+            }
+        }
+        let restore = match self.config.restore_on_completion {
+            true => window::fetch_minimized(window::Id::MAIN, PomodoroMessage::CompletedWhileMinimized),
+            false => Command::none(),
+        };
+        match attention::request(&self.config).filter(|_| !self.focused) {
+            Some(request) => {
+                self.attention_requested = true;
+                Command::batch([request, restore, clicked])
+            }
+            None => Command::batch([restore, clicked]),
+        }
+    }
+
+    /// `completion_messages` for `finished` filled in for the session that just ended. `None`
+    /// without one, or when it couldn't be read, which startup already reported.
+    fn completion_message(&self, finished: Mode) -> Option<String> {
+        let template = Template::parse(self.config.completion_messages.get(finished)?).ok()?;
+        Some(template.render(|variable| match variable {
+            Variable::Mode => self.config.label_for(finished).to_string(),
+            Variable::Duration => daily::hours_minutes(self.session_total.as_secs()),
+            Variable::CountToday => self.daily.counters.pomodoros_completed.to_string(),
+            Variable::Task => self.tasks.current().map(|task| task.name.clone()).unwrap_or_default(),
+        }))
+    }
+
+    /// Heading of the notification for the session that just completed.
+    fn completion_summary(&self) -> String {
+        self.completion_message(self.mode).unwrap_or_else(|| format!("{} over", self.config.label_for(self.mode)))
+    }
+
+    /// Names the work session that just started after the window in front, with
+    /// `name_from_active_window` and no current task. Asking can take a moment, so it happens
+    /// off the UI thread.
+    fn name_session(&self) -> Command<PomodoroMessage> {
+        let wanted = self.mode == Mode::Work && self.config.name_from_active_window && self.tasks.current().is_none() && !self.test_session;
+        match self.session_started_at.filter(|_| wanted) {
+            Some(started_at) => Command::perform(active_window::fetch_title(), move |title| PomodoroMessage::ActiveWindowTitle(started_at, title)),
+            None => Command::none(),
+        }
+    }
+
+    /// Whether it's within `silent_hours` now, when nothing rings or notifies.
+    fn silent_hours(&self) -> bool {
+        self.config.silent_hours.as_ref().is_some_and(|hours| hours.contains(Local::now().time()))
+    }
+
+    /// Whether a completion may ring and notify, at most once every `ALERT_GAP`. Counts this one
+    /// when it may.
+    fn alert_due(&mut self) -> bool {
+        let now = Instant::now();
+        let due = self.last_alert.is_none_or(|last| now.duration_since(last) >= ALERT_GAP);
+        if due {
+            self.last_alert = Some(now);
+        }
+        due
+    }
+
+    /// Starts the next session of the routine, or stops once it has run out. Returns false, doing
+    /// nothing, when no routine is running.
+    fn advance_routine(&mut self) -> bool {
+        let Some(routine) = self.routine.as_mut() else {
+            return false;
+        };
+        match routine.advance() {
+            Some(step) => {
+                self.switch_mode(step.mode);
+                self.start();
+            }
+            None => {
+                let name = self.routine.take().map(|routine| routine.name).unwrap_or_default();
+                self.log_session(false);
+                self.state = State::Completed { finished: self.mode };
+                self.stopped_at = Some(Instant::now());
+                self.save_snapshot();
+                if !self.silent_hours() {
+                    notify::send("Routine finished", format!("That was all of {}", name));
+                }
+            }
+        }
+        true
+    }
+
+    /// Writes the session in progress to the log, if there is one, and adds it to today's totals.
+    fn log_session(&mut self, completed: bool) {
+        let activities = self.activities_note();
+        // Ticked off for this break only; the next one starts with a fresh list
+        self.activities_done.clear();
+        let Some(started_at) = self.session_started_at.take() else {
+            return;
+        };
+        let test = std::mem::take(&mut self.test_session);
+        let extension = std::mem::take(&mut self.extending);
+        if !completed && self.in_grace_period() {
+            // Cancelled rather than abandoned: as if it was never started
+            if self.mode == Mode::Work && !test && !extension {
+                self.daily.counters.pomodoros_started = self.daily.counters.pomodoros_started.saturating_sub(1);
+                self.save_daily();
+            }
+            return;
+        }
+        let record = SessionRecord {
+            started_at,
+            ended_at: Local::now(),
+            mode: self.mode,
+            planned: self.session_total,
+            actual: self.session_total.saturating_sub(self.timer),
+            completed,
+            kind: match (test, extension) {
+                (true, _) => RecordKind::Test,
+                (false, true) => RecordKind::Extension,
+                (false, false) => RecordKind::Session,
+            },
+            note: match self.mode {
+                Mode::Work if self.config.name_from_active_window && self.tasks.current().is_none() => self.session_label.trim().to_string(),
+                _ => activities.unwrap_or_default(),
+            },
+            paused: self.paused_this_session,
+        };
+        if !test {
+            self.record_daily(&record);
+        }
+        // Only one note is asked for at a time; an unanswered prompt goes in as it is
+        self.write_pending_note();
+        if completed && self.mode == Mode::Work && self.config.session_notes && !test && !extension {
+            // The session's name, if any, is where the note starts
+            self.note_input = record.note.clone();
+            self.pending_note = Some(record);
+            return;
+        }
+        self.write_record(&record);
+    }
+
+    /// Logs the time between `started_at` and `ended_at` as a gap in the current session.
+    fn log_gap(&self, started_at: DateTime<Local>, ended_at: DateTime<Local>, note: &str) {
+        let away = (ended_at - started_at).to_std().unwrap_or_default();
+        let record = SessionRecord { started_at, ended_at, mode: self.mode, planned: Duration::ZERO, actual: away, completed: false, kind: RecordKind::Gap, note: note.to_string(), paused: true };
+        self.write_record(&record);
+    }
+
+    /// How much of the checklist got done, as the log note for a break.
+    fn activities_note(&self) -> Option<String> {
+        if !self.mode.is_break() || !self.config.log_break_activities || self.activities_done.is_empty() {
+            return None;
+        }
+        let done = self.activities_done.iter().filter(|&&done| done).count();
+        Some(format!("{} of {} break activities", done, self.activities_done.len()))
+    }
+
+    /// Whether the session was started less than `start_grace_seconds` ago.
+    fn in_grace_period(&self) -> bool {
+        self.started.is_some_and(|started| started.elapsed() < Duration::from_secs(self.config.start_grace_seconds))
+    }
+
+    /// Writes the session waiting on its note, with whatever has been typed so far.
+    fn write_pending_note(&mut self) {
+        if let Some(mut record) = self.pending_note.take() {
+            record.note = std::mem::take(&mut self.note_input).trim().to_string();
+            self.write_record(&record);
+        }
+    }
+
+    fn write_record(&self, record: &SessionRecord) {
+        let Some(data_dir) = self.persistence.data_dir() else {
+            return;
+        };
+        let rounding = Rounding { step: Duration::from_secs(self.config.log_rounding_minutes * 60), target: self.config.log_rounding_applies_to };
+        const DAY: u64 = 24 * 60 * 60;
+        let rotation = Rotation { max_bytes: self.config.log_rotate_kb * 1024, max_age: Duration::from_secs(self.config.log_rotate_days * DAY), keep: Duration::from_secs(self.config.log_keep_days * DAY) };
+        if let Err(err) = session_log::append(data_dir, record, rounding, rotation) {
+            eprintln!("Could not log session: {}", err);
+        }
+    }
+
+    fn record_daily(&mut self, record: &SessionRecord) {
+        self.daily.roll_over_at(record.ended_at);
+        match self.mode {
+            Mode::Work => self.daily.counters.work_secs += record.actual.as_secs(),
+            Mode::ShortBreak | Mode::LongBreak => self.daily.counters.break_secs += record.actual.as_secs(),
+        }
+        self.exact_actual.push((record.started_at, record.actual));
+        self.save_daily();
+        if self.mode == Mode::Work {
+            self.check_break_ratio();
+        }
+    }
+
+    /// Takes a session removed from the log back off today's counters, if it was from today,
+    /// and off the current task if it was a completed work session. Counters that are already at
+    /// zero stay there.
+    fn unrecord(&mut self, record: &SessionRecord) {
+        self.daily.roll_over();
+        if !matches!(record.kind, RecordKind::Session | RecordKind::Extension) || record.ended_at.date_naive() != self.daily.date {
+            return;
+        }
+        // Sessions from before the app was last started only have the logged time to go by
+        let actual = match self.exact_actual.iter().rposition(|&(started_at, _)| started_at == record.started_at) {
+            Some(i) => self.exact_actual.remove(i).1,
+            None => record.actual,
+        };
+        let counters = &mut self.daily.counters;
+        match record.mode {
+            Mode::Work if record.kind == RecordKind::Extension => counters.work_secs = counters.work_secs.saturating_sub(actual.as_secs()),
+            Mode::Work => {
+                counters.work_secs = counters.work_secs.saturating_sub(actual.as_secs());
+                counters.pomodoros_started = counters.pomodoros_started.saturating_sub(1);
+                if record.completed {
+                    counters.pomodoros_completed = counters.pomodoros_completed.saturating_sub(1);
+                    if !record.paused {
+                        counters.perfect_sessions = counters.perfect_sessions.saturating_sub(1);
+                    }
+                    counters.work_since_long_break = counters.work_since_long_break.saturating_sub(1);
+                    self.completed_work_sessions = self.completed_work_sessions.saturating_sub(1);
+                    self.tasks.uncount_session();
+                    self.save_tasks();
+                }
+            }
+            Mode::ShortBreak | Mode::LongBreak => counters.break_secs = counters.break_secs.saturating_sub(actual.as_secs()),
+        }
+        self.save_daily();
+        self.save_snapshot();
+    }
+
+    /// Raises the break ratio warning if today's breaks are too short for the work done, at most
+    /// once per cooldown.
+    fn check_break_ratio(&mut self) {
+        let counters = &self.daily.counters;
+        let work = counters.work_secs as f32;
+        if self.config.min_break_ratio <= 0.0 || work < BREAK_RATIO_GRACE.as_secs_f32() || counters.break_secs as f32 >= work * self.config.min_break_ratio {
+            return;
+        }
+        let cooldown = Duration::from_secs(self.config.break_warning_cooldown_minutes * 60);
+        if self.last_break_warning.is_some_and(|at| at.elapsed() < cooldown) {
+            return;
+        }
+        self.last_break_warning = Some(Instant::now());
+        self.break_warning = true;
+        if self.config.break_warning_notification && !self.silent_hours() {
+            notify::send("Time for a break?", BREAK_WARNING);
+        }
+    }
+
+    /// Today's summary, unless it has been sent already or nothing was completed yet. Marks it sent.
+    fn take_summary(&mut self) -> Option<String> {
+        if self.daily.counters.summary_sent || self.daily.counters.pomodoros_completed == 0 {
+            return None;
+        }
+        self.daily.counters.summary_sent = true;
+        self.save_daily();
+        Some(self.daily.summary())
+    }
+
+    fn save_tasks(&self) {
+        if let Some(data_dir) = self.persistence.data_dir() {
+            if let Err(err) = self.tasks.save(data_dir) {
+                eprintln!("Could not save tasks: {}", err);
+            }
+        }
+    }
+
+    fn save_daily(&self) {
+        if let Some(data_dir) = self.persistence.data_dir() {
+            if let Err(err) = self.daily.save(data_dir) {
+                eprintln!("Could not save daily counters: {}", err);
+            }
+        }
+    }
+
+    /// Saves the final snapshot, sends the daily summary if it's due, and closes the window. Both
+    /// happen on a helper thread so a slow disk or notification daemon can hold up quitting for at
+    /// most `SHUTDOWN_TIMEOUT`, as can the focus mode commands still queued.
+    fn shutdown(&mut self) -> Command<PomodoroMessage> {
+        if !self.shut_down {
+            self.shut_down = true;
+            self.write_pending_note();
+            self.daily.roll_over();
+            let summary = if self.config.daily_summary_on_quit && !self.silent_hours() { self.take_summary() } else { None };
+            if std::mem::take(&mut self.focus_active) {
+                self.focus_commands.push(&self.config.focus_unblock_cmd);
+            }
+            if !std::mem::take(&mut self.focus_commands).finish(SHUTDOWN_TIMEOUT) {
+                eprintln!("Gave up waiting for the focus mode commands to finish");
+            }
+            let snapshot = self.persistence.data_dir().map(|data_dir| (data_dir.to_path_buf(), self.snapshot()));
+            if snapshot.is_some() || summary.is_some() {
+                let (done, finished) = mpsc::channel();
+                thread::spawn(move || {
+                    if let Some((data_dir, snapshot)) = snapshot {
+                        if let Err(err) = snapshot.save(&data_dir) {
+                            eprintln!("Could not save snapshot: {}", err);
+                        }
+                    }
+                    if let Some(summary) = summary {
+                        notify::show("Rusty Pomodoro", &summary);
+                    }
+                    let _ = done.send(());
+                });
+                if finished.recv_timeout(SHUTDOWN_TIMEOUT).is_err() {
+                    eprintln!("Gave up waiting for the snapshot to be saved");
+                }
+            }
+        }
+        window::close(window::Id::MAIN)
+    }
+
+    /// The click of `click_feedback`, for a button or shortcut that was pressed.
+    /// Quiet during `silent_hours`, like the other sounds.
+    fn feedback(&mut self) {
+        if self.config.click_feedback && self.config.sound_theme != SoundTheme::Silent && !self.silent_hours() {
+            self.clicker.click();
+        }
+    }
+
+    /// Runs the focus mode commands so distractions are blocked exactly while work is running.
+    /// Called after every message, so Reset, Skip and the like unblock as well.
+    fn sync_focus(&mut self) {
+        let blocking = self.config.focus_mode && self.mode == Mode::Work && self.state == State::Running && !self.shut_down;
+        if blocking != self.focus_active {
+            self.focus_active = blocking;
+            self.focus_commands.push(if blocking { &self.config.focus_block_cmd } else { &self.config.focus_unblock_cmd });
+        }
+    }
+
+    fn save_snapshot(&self) {
+        if let Some(data_dir) = self.persistence.data_dir() {
+            if let Err(err) = self.snapshot().save(data_dir) {
+                eprintln!("Could not save snapshot: {}", err);
+            }
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            mode: self.mode,
+            state: match self.state {
+                State::Idle | State::Overtime | State::Completed { .. } => SavedState::Idle,
+                State::Held => SavedState::Held,
+                State::Paused(PauseReason::Manual) => SavedState::PausedManually,
+                State::Paused(_) | State::Resuming { .. } => SavedState::Paused,
+                State::Running => SavedState::Running,
+            },
+            remaining_ms: self.timer.as_millis() as u64,
+            session_total_ms: self.session_total.as_millis() as u64,
+            completed_work_sessions: self.completed_work_sessions,
+            saved_at: snapshot::unix_now(),
+        }
+    }
+
+    /// Picks up where the last run left off. An interrupted session comes back paused, and if real
+    /// time has passed since, the user is asked whether it should count against the timer.
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.mode = snapshot.mode;
+        self.timer = Duration::from_millis(snapshot.remaining_ms);
+        self.session_total = Duration::from_millis(snapshot.session_total_ms);
+        self.completed_work_sessions = snapshot.completed_work_sessions;
+        // A set left unfinished on an earlier day starts over, unless the user asks to continue it
+        let from_earlier_day = snapshot.saved_on().is_some_and(|saved_on| saved_on < Local::now().date_naive());
+        let set_unfinished = self.technique.sessions_before_long_break.filter(|&every| every > 0).is_some_and(|every| !self.completed_work_sessions.is_multiple_of(every));
+        if from_earlier_day && set_unfinished {
+            self.previous_set = Some(self.completed_work_sessions);
+            self.completed_work_sessions = 0;
+        }
+        if snapshot.state == SavedState::Held {
+            self.state = State::Held;
+            return;
+        }
+        if snapshot.state != SavedState::Idle {
+            self.state = match snapshot.state {
+                SavedState::PausedManually => State::Paused(PauseReason::Manual),
+                _ => State::Paused(PauseReason::Idle),
+            };
+            self.started = None;
+            self.paused_this_session = true;
+            let elapsed = self.session_total.saturating_sub(self.timer);
+            self.session_started_at = chrono::Duration::from_std(elapsed).ok().map(|elapsed| Local::now() - elapsed);
+            let away = snapshot.age();
+            let saved_at = DateTime::from_timestamp(snapshot.saved_at as i64, 0).map(|saved_at| saved_at.with_timezone(&Local));
+            if let Some(saved_at) = saved_at.filter(|_| self.config.log_away_gaps && snapshot.state == SavedState::Running) {
+                self.log_gap(saved_at, Local::now(), "app closed");
+            }
+            // A session the user paused wasn't counting down while the app was closed
+            if away >= Duration::from_secs(60) && snapshot.state != SavedState::PausedManually {
+                self.catch_up = Some(away);
+            }
+        }
+    }
+
+    /// Writes today's completed sessions to an `.ics` file in the data directory.
+    fn export_calendar(&self) -> std::io::Result<PathBuf> {
+        let data_dir = self.persistence.data_dir().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no data directory"))?;
+        let today = Local::now().date_naive();
+        let mut records = session_log::read_day(data_dir, today)?;
+        records.retain(|record| record.completed && record.kind == RecordKind::Session);
+        let path = data_dir.join(format!("sessions-{}.ics", today));
+        std::fs::write(&path, ics::calendar(&records, chrono::Utc::now()))?;
+        Ok(path)
+    }
+
+    fn save_config(&self) {
+        let Some(path) = self.persistence.config_file() else {
+            return;
+        };
+        if let Err(err) = self.config.save(path) {
+            eprintln!("Could not save config: {}", err);
+        }
+    }
+
+    /// Fraction of the current session that has elapsed, from 0 to 1. Nothing has elapsed while
+    /// idle, whatever Reset left on the countdown.
+    fn progress(&self) -> f32 {
+        if self.state == State::Idle || self.session_total.is_zero() {
+            return 0.0;
+        }
+        1.0 - (self.timer.as_secs_f32() / self.session_total.as_secs_f32()).min(1.0)
+    }
+
+    /// Picks a random break tip, never the same one twice in a row.
+    fn pick_tip(&mut self) {
+        let count = self.config.break_tips.len();
+        self.tip = match (count, self.tip) {
+            (0, _) => None,
+            (1, _) => Some(0),
+            (_, Some(last)) if last < count => {
+                let pick = self.rng.below(count - 1);
+                Some(if pick >= last { pick + 1 } else { pick })
+            }
+            _ => Some(self.rng.below(count)),
+        };
+    }
+}
+
+/// Rough words for the time left: "almost done" in the last minute, "a few minutes left" up
+/// to five, then minutes to the nearest five and, from 55 minutes on, hours.
+fn fuzzy_label(remaining: Duration) -> String {
+    let secs = remaining.as_secs();
+    match secs {
+        0..=59 => String::from("almost done"),
+        60..=299 => String::from("a few minutes left"),
+        300..=3299 => format!("about {} min left", (secs + 150) / 300 * 5),
+        3300..=5399 => String::from("about an hour left"),
+        _ => format!("about {} hours left", (secs + 1800) / 3600),
+    }
+}
+
+/// Parses a length typed over the countdown: whole minutes like "25", or minutes and seconds
+/// like "12:30". Returns `None` for anything else, zero, or more than `MAX_DURATION`.
+fn parse_time(text: &str) -> Option<Duration> {
+    let (minutes, seconds) = match text.trim().split_once(':') {
+        Some((minutes, seconds)) => (minutes.trim(), Some(seconds.trim())),
+        None => (text.trim(), None),
+    };
+    let digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+    if !digits(minutes) || !seconds.is_none_or(digits) {
+        return None;
+    }
+    let seconds: u64 = seconds.map_or(Some(0), |seconds| seconds.parse().ok())?;
+    if seconds >= 60 {
+        return None;
+    }
+    let length = Duration::from_secs(minutes.parse::<u64>().ok()?.checked_mul(60)?.checked_add(seconds)?);
+    (!length.is_zero() && length <= config::MAX_DURATION).then_some(length)
+}
+
+fn time_input_id() -> text_input::Id {
+    text_input::Id::new("time")
+}
+
+/// Formats a duration as minutes and seconds, e.g. "4:05".
+fn short_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:0>2}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(config: Config, persistence: Persistence) -> Flags {
+        Flags {
+            config,
+            persistence,
+            #[cfg(all(feature = "panel-dbus", target_os = "linux"))]
+            panel: panel::Publisher::default(),
+        }
+    }
+
+    fn app_from(config: Config) -> Pomodoro {
+        Pomodoro::new(flags(Config { sound_theme: SoundTheme::Silent, ..config }, Persistence::Memory)).0
+    }
+
+    /// The mode, the state and how far into the set the app is.
+    fn step(app: &Pomodoro) -> (Mode, State, u32) {
+        (app.mode, app.state, app.completed_work_sessions)
+    }
+
+    /// Lets the running session count all the way down.
+    fn finish(app: &mut Pomodoro) {
+        app.last_tick -= app.timer + Duration::from_secs(1);
+        let _ = app.update(PomodoroMessage::Tick);
+    }
+
+    /// Finishes a work session and then the break after it, returning the state and mode after each.
+    fn work_then_break(app: &mut Pomodoro) -> [(State, Mode); 2] {
+        let _ = app.update(PomodoroMessage::Start);
+        finish(app);
+        let after_work = (app.state, app.mode);
+        if let State::Completed { .. } = app.state {
+            let _ = app.update(PomodoroMessage::SwitchMode(Mode::ShortBreak));
+            let _ = app.update(PomodoroMessage::Start);
+        }
+        finish(app);
+        [after_work, (app.state, app.mode)]
+    }
+
+    #[test]
+    fn nothing_starts_automatically_by_default() {
+        let mut app = app_from(Config { auto_start_breaks: false, auto_start_work: false, ..Config::default() });
+        assert_eq!(work_then_break(&mut app), [(State::Completed { finished: Mode::Work }, Mode::Work), (State::Completed { finished: Mode::ShortBreak }, Mode::ShortBreak)]);
+    }
+
+    #[test]
+    fn auto_start_breaks_only_starts_the_break() {
+        let mut app = app_from(Config { auto_start_breaks: true, auto_start_work: false, ..Config::default() });
+        assert_eq!(work_then_break(&mut app), [(State::Running, Mode::ShortBreak), (State::Completed { finished: Mode::ShortBreak }, Mode::ShortBreak)]);
+    }
+
+    #[test]
+    fn auto_start_work_only_starts_the_work_session() {
+        let mut app = app_from(Config { auto_start_breaks: false, auto_start_work: true, ..Config::default() });
+        assert_eq!(work_then_break(&mut app), [(State::Completed { finished: Mode::Work }, Mode::Work), (State::Running, Mode::Work)]);
+    }
+
+    #[test]
+    fn both_flags_start_everything() {
+        let mut app = app_from(Config { auto_start_breaks: true, auto_start_work: true, ..Config::default() });
+        assert_eq!(work_then_break(&mut app), [(State::Running, Mode::ShortBreak), (State::Running, Mode::Work)]);
+    }
+
+    #[test]
+    fn restart_refills_timer_and_keeps_running() {
+        let mut app = app_from(Config::default());
+        let _ = app.update(PomodoroMessage::Start);
+        app.last_tick -= Duration::from_secs(90);
+        let _ = app.update(PomodoroMessage::Tick);
+        assert!(app.timer < app.session_total);
+
+        let _ = app.update(PomodoroMessage::Restart);
+        assert_eq!(app.timer, app.durations.work);
+        assert_eq!(app.state, State::Running);
+    }
+
+    #[test]
+    fn restarting_a_completed_session_counts_it_as_started_again() {
+        let mut app = app_from(Config::default());
+        let _ = app.update(PomodoroMessage::Start);
+        let _ = app.update(PomodoroMessage::Pause);
+        let _ = app.update(PomodoroMessage::Resume);
+        finish(&mut app);
+        assert_eq!(app.state, State::Completed { finished: Mode::Work });
+
+        let _ = app.update(PomodoroMessage::Restart);
+        assert_eq!((app.mode, app.state, app.timer), (Mode::Work, State::Running, app.durations.work));
+        assert!(!app.paused_this_session);
+        finish(&mut app);
+        let counters = &app.daily.counters;
+        assert_eq!((counters.pomodoros_started, counters.pomodoros_completed, counters.perfect_sessions), (2, 2, 1));
+    }
+
+    #[test]
+    fn restarting_a_test_session_keeps_it_a_test() {
+        let mut app = app_from(Config::default());
+        let _ = app.update(PomodoroMessage::StartTestSession);
+        let _ = app.update(PomodoroMessage::Restart);
+        assert_eq!((app.state, app.timer, app.test_session), (State::Running, TEST_SESSION, true));
+        finish(&mut app);
+        let counters = &app.daily.counters;
+        assert_eq!((counters.pomodoros_started, counters.pomodoros_completed, app.completed_work_sessions), (0, 0, 0));
+    }
+
+    #[test]
+    fn auto_starting_everything_runs_a_full_set_with_a_long_break_at_the_end() {
+        let mut app = app_from(Config { auto_start_breaks: true, auto_start_work: true, ..Config::default() });
+        let _ = app.update(PomodoroMessage::Start);
+        let steps: Vec<_> = (0..8).map(|_| {
+            finish(&mut app);
+            step(&app)
+        }).collect();
+        assert_eq!(steps, [
+            (Mode::ShortBreak, State::Running, 1),
+            (Mode::Work, State::Running, 1),
+            (Mode::ShortBreak, State::Running, 2),
+            (Mode::Work, State::Running, 2),
+            (Mode::ShortBreak, State::Running, 3),
+            (Mode::Work, State::Running, 3),
+            (Mode::LongBreak, State::Running, 4),
+            (Mode::Work, State::Running, 4),
+        ]);
+        assert_eq!(app.daily.counters.pomodoros_started, 5);
+        assert_eq!(app.daily.counters.pomodoros_completed, 4);
+    }
+
+    #[test]
+    fn start_next_walks_the_same_cycle_by_hand() {
+        let mut app = app_from(Config::default());
+        let _ = app.update(PomodoroMessage::Start);
+        let mut modes = Vec::new();
+        for _ in 0..8 {
+            finish(&mut app);
+            assert_eq!(app.state, State::Completed { finished: app.mode });
+            let _ = app.update(PomodoroMessage::StartNext);
+            modes.push(app.mode);
+        }
+        let (work, short, long) = (Mode::Work, Mode::ShortBreak, Mode::LongBreak);
+        assert_eq!(modes, [short, work, short, work, short, work, long, work]);
+    }
+
+    #[test]
+    fn skipping_work_does_not_count_towards_the_long_break() {
+        let mut app = app_from(Config { auto_start_breaks: true, auto_start_work: true, ..Config::default() });
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        let _ = app.update(PomodoroMessage::Skip);
+        assert_eq!(step(&app), (Mode::Work, State::Idle, 1));
+
+        let _ = app.update(PomodoroMessage::Start);
+        let _ = app.update(PomodoroMessage::Skip);
+        assert_eq!(step(&app), (Mode::ShortBreak, State::Idle, 1));
+        assert_eq!(app.daily.counters.pomodoros_completed, 1);
+
+        let _ = app.update(PomodoroMessage::Start);
+        for _ in 0..5 {
+            finish(&mut app);
+        }
+        assert_eq!(step(&app), (Mode::Work, State::Running, 3));
+        finish(&mut app);
+        assert_eq!(step(&app), (Mode::LongBreak, State::Running, 4));
+    }
+
+    #[test]
+    fn switching_modes_interrupts_the_cycle_without_counting() {
+        let mut app = app_from(Config { auto_start_breaks: true, auto_start_work: true, ..Config::default() });
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        let _ = app.update(PomodoroMessage::SwitchMode(Mode::Work));
+        assert_eq!(step(&app), (Mode::Work, State::Idle, 1));
+        assert_eq!(app.timer, app.durations.work);
+
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        assert_eq!(step(&app), (Mode::ShortBreak, State::Running, 2));
+    }
+
+    #[test]
+    fn reset_starts_the_set_over_and_keeps_todays_totals() {
+        let mut app = app_from(Config::default());
+        for _ in 0..3 {
+            let _ = app.update(PomodoroMessage::Start);
+            finish(&mut app);
+            let _ = app.update(PomodoroMessage::StartNext);
+            finish(&mut app);
+            let _ = app.update(PomodoroMessage::SwitchMode(Mode::Work));
+        }
+        let _ = app.update(PomodoroMessage::Start);
+        assert_eq!(step(&app), (Mode::Work, State::Running, 3));
+
+        let _ = app.update(PomodoroMessage::Reset);
+        assert_eq!(step(&app), (Mode::Work, State::Idle, 0));
+        assert_eq!(app.timer, app.durations.work);
+        assert_eq!(app.daily.counters.pomodoros_completed, 3);
+
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        assert_eq!(step(&app), (Mode::Work, State::Completed { finished: Mode::Work }, 1));
+        assert_eq!(app.next_mode(app.mode), Mode::ShortBreak);
+    }
+
+    #[test]
+    fn overtime_and_hold_wait_for_the_next_session() {
+        for behavior in [CompletionBehavior::Overtime, CompletionBehavior::HoldUntilDismissed] {
+            let completion = config::PerMode { work: Some(behavior), ..config::PerMode::default() };
+            let mut app = app_from(Config { auto_start_breaks: true, auto_start_work: true, completion, ..Config::default() });
+            let _ = app.update(PomodoroMessage::Start);
+            finish(&mut app);
+            let waiting = if behavior == CompletionBehavior::Overtime { State::Overtime } else { State::Held };
+            assert_eq!(step(&app), (Mode::Work, waiting, 1));
+
+            app.last_tick -= Duration::from_secs(30);
+            let _ = app.update(PomodoroMessage::Tick);
+            assert_eq!(app.overtime >= Duration::from_secs(30), behavior == CompletionBehavior::Overtime);
+
+            let _ = app.update(PomodoroMessage::StartNext);
+            assert_eq!(step(&app), (Mode::ShortBreak, State::Running, 1));
+            finish(&mut app);
+            assert_eq!(step(&app), (Mode::Work, State::Running, 1));
+        }
+    }
+
+    #[test]
+    fn notification_buttons_only_act_on_the_completion_they_came_with() {
+        let click = |completion, action: &str| PomodoroMessage::NotificationAction(completion, Some(String::from(action)));
+        let mut app = app_from(Config::default());
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        let _ = app.update(click(1, "more_time"));
+        assert_eq!((app.mode, app.state, app.timer), (Mode::Work, State::Running, MORE_TIME));
+        // Clicked late, once the window has moved on
+        let _ = app.update(click(1, "start_next"));
+        assert_eq!((app.mode, app.state), (Mode::Work, State::Running));
+        finish(&mut app);
+        // The extra time is part of the first session, not a second one
+        assert_eq!((step(&app), app.daily.counters.pomodoros_started, app.daily.counters.pomodoros_completed), ((Mode::Work, State::Completed { finished: Mode::Work }, 1), 1, 1));
+        // A click left over from the first completion
+        let _ = app.update(click(1, "start_next"));
+        assert_eq!(app.state, State::Completed { finished: Mode::Work });
+        let _ = app.update(click(2, "start_next"));
+        assert_eq!((app.mode, app.state), (Mode::ShortBreak, State::Running));
+    }
+
+    #[test]
+    fn the_time_bank_only_pays_into_a_session_underway() {
+        let mut app = app_from(Config { time_bank_max_minutes: 30, ..Config::default() });
+        app.daily.counters.banked_secs = 600;
+        let _ = app.update(PomodoroMessage::UseBank);
+        let _ = app.update(PomodoroMessage::Start);
+        assert_eq!((app.timer, app.daily.counters.banked_secs), (app.durations.work, 600));
+        let _ = app.update(PomodoroMessage::UseBank);
+        assert_eq!(app.session_total, app.durations.work + BANK_WITHDRAWAL);
+        assert_eq!(app.daily.counters.banked_secs, 600 - BANK_WITHDRAWAL.as_secs());
+    }
+
+    #[test]
+    fn a_task_session_is_not_logged_under_an_old_window_title() {
+        let mut app = app_from(Config { name_from_active_window: true, session_notes: true, ..Config::default() });
+        app.session_label = String::from("Old window");
+        app.tasks.tasks.push(tasks::Task { name: String::from("Taxes"), estimate: 2, completed: 0 });
+        app.tasks.current = Some(0);
+        let _ = app.update(PomodoroMessage::Start);
+        let started_at = app.session_started_at.expect("a session underway");
+        let _ = app.update(PomodoroMessage::ActiveWindowTitle(started_at - chrono::Duration::minutes(30), Some(String::from("Late answer"))));
+        assert_eq!(app.session_label, "");
+        finish(&mut app);
+        assert_eq!(app.pending_note.as_ref().map(|record| record.note.as_str()), Some(""));
+    }
+
+    #[test]
+    fn lengths_from_the_timer_screen_are_validated_too() {
+        let mut app = app_from(Config::default());
+        let _ = app.update(PomodoroMessage::SetMinutes(5000));
+        assert_eq!((app.durations.work, app.timer), (Duration::from_secs(25 * 60), Duration::from_secs(25 * 60)));
+        assert!(app.error.as_deref().is_some_and(|error| error.contains("the most is 1440")));
+        app.start_session(Duration::ZERO);
+        assert_eq!(app.state, State::Idle);
+    }
+
+    #[test]
+    fn roulette_stops_at_the_longest_session_and_hides_it_everywhere() {
+        let roulette = config::Roulette { min_minutes: u64::MAX - 1, max_minutes: u64::MAX, hide_length: true };
+        let mut app = app_from(Config { roulette: Some(roulette), ..Config::default() });
+        assert!(app.error.as_deref().is_some_and(|error| error.contains("roulette")));
+        let _ = app.update(PomodoroMessage::Start);
+        assert_eq!(app.timer, config::MAX_DURATION);
+        assert!(!app.title().contains('#') && !app.title().contains('-'));
+    }
+
+    #[test]
+    fn plain_shortcuts_only_work_on_the_timer_screens() {
+        let space = || PomodoroMessage::KeyPressed(keyboard::Key::Named(keyboard::key::Named::Space), keyboard::Modifiers::empty());
+        let mut app = app_from(Config::default());
+        app.screen = Screen::Settings;
+        let _ = app.update(space());
+        assert_eq!(app.state, State::Idle);
+        let _ = app.update(PomodoroMessage::KeyPressed(keyboard::Key::Named(keyboard::key::Named::F9), keyboard::Modifiers::empty()));
+        assert!(app.panicked.is_some());
+
+        app.screen = Screen::Timer;
+        let _ = app.update(space());
+        assert_eq!(app.state, State::Running);
+    }
+
+    #[test]
+    fn a_pressed_button_does_what_its_message_says() {
+        let mut app = app_from(Config { click_feedback: true, ..Config::default() });
+        let _ = app.update(PomodoroMessage::Start.pressed());
+        assert_eq!(app.state, State::Running);
+        let _ = app.update(PomodoroMessage::RunAction(Box::new(PomodoroMessage::Pause)).pressed());
+        assert_eq!(app.state, State::Paused(PauseReason::Manual));
+    }
+
+    #[test]
+    fn reset_forgets_when_the_last_session_stopped() {
+        let mut app = app_from(Config { reset_behavior: ResetBehavior::RefillAndStop, ..Config::default() });
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        assert!(app.stopped_at.is_some());
+        app.reset(app.config.reset_behavior);
+        assert_eq!((app.state, app.stopped_at), (State::Idle, None));
+    }
+
+    #[test]
+    fn the_upcoming_break_is_the_one_that_follows() {
+        for long_break_after in [0, 3] {
+            let mut app = app_from(Config { auto_start_breaks: true, auto_start_work: true, long_break_after, ..Config::default() });
+            let _ = app.update(PomodoroMessage::Start);
+            for _ in 0..6 {
+                let upcoming = app.upcoming_break();
+                finish(&mut app);
+                assert_eq!(app.mode, upcoming);
+                finish(&mut app);
+            }
+        }
+
+        let steps = vec![routine::Step { mode: Mode::Work, minutes: 10 }, routine::Step { mode: Mode::LongBreak, minutes: 3 }];
+        let routines = std::collections::BTreeMap::from([(String::from("sprint"), routine::Routine { steps, repeat: 1 })]);
+        let mut app = app_from(Config { routines, ..Config::default() });
+        let _ = app.update(PomodoroMessage::StartRoutine(String::from("sprint")));
+        assert_eq!(app.upcoming_break(), Mode::LongBreak);
+        assert_eq!(app.cycle_left(), Some(Duration::from_secs(13 * 60)));
+    }
+
+    #[test]
+    fn break_activities_are_cleared_once_the_break_is_over() {
+        let mut app = app_from(Config { break_activities: vec![String::from("Stretch"), String::from("Water")], ..Config::default() });
+        for switched_away in [false, true] {
+            let _ = app.update(PomodoroMessage::SwitchMode(Mode::ShortBreak));
+            let _ = app.update(PomodoroMessage::Start);
+            let _ = app.update(PomodoroMessage::ToggleActivity(0, true));
+            assert_eq!(app.activities_done, [true, false]);
+            match switched_away {
+                true => {
+                    let _ = app.update(PomodoroMessage::SwitchMode(Mode::Work));
+                }
+                false => finish(&mut app),
+            }
+            assert!(app.activities_done.is_empty());
+        }
+    }
+
+    #[test]
+    fn a_held_session_is_still_held_after_a_restart() {
+        let completion = config::PerMode { work: Some(CompletionBehavior::HoldUntilDismissed), ..config::PerMode::default() };
+        let config = Config { completion, ..Config::default() };
+        let mut app = app_from(config.clone());
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        assert_eq!(app.state, State::Held);
+
+        let mut restarted = app_from(config);
+        restarted.restore(app.snapshot());
+        assert_eq!(step(&restarted), (Mode::Work, State::Held, 1));
+        assert_eq!(restarted.catch_up, None);
+    }
+
+    #[test]
+    fn a_manual_pause_is_still_manual_after_a_restart() {
+        let mut app = app_from(Config::default());
+        let _ = app.update(PomodoroMessage::Start);
+        let _ = app.update(PomodoroMessage::Pause);
+        let mut snapshot = app.snapshot();
+        snapshot.saved_at -= 600;
+
+        let mut restarted = app_from(Config::default());
+        restarted.restore(snapshot);
+        assert_eq!((restarted.state, restarted.catch_up), (State::Paused(PauseReason::Manual), None));
+    }
+
+    #[test]
+    fn the_welcome_screen_shows_on_a_first_launch_after_marking_the_data_directory() {
+        let dir = std::env::temp_dir().join(format!("rusty-pomodoro-first-run-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let in_use = persistence::InUse::mark(&dir).unwrap();
+        let persistence = Persistence::Disk { config_file: dir.join("config.toml"), data_dir: dir.clone() };
+        let app = Pomodoro::new(flags(Config { sound_theme: SoundTheme::Silent, ..Config::default() }, persistence.clone())).0;
+        assert_eq!(app.screen, Screen::Welcome);
+
+        welcome::dismiss(&dir).unwrap();
+        let app = Pomodoro::new(flags(Config { sound_theme: SoundTheme::Silent, ..Config::default() }, persistence)).0;
+        assert_eq!(app.screen, Screen::Timer);
+        drop(in_use);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn nothing_has_elapsed_after_a_clearing_reset() {
+        let mut app = reset_halfway(ResetBehavior::Clear);
+        assert_eq!((app.state, app.timer), (State::Idle, Duration::ZERO));
+        assert_eq!(app.progress(), 0.0);
+        let _ = app.update(PomodoroMessage::Start);
+        app.last_tick -= app.durations.work / 4;
+        let _ = app.update(PomodoroMessage::Tick);
+        assert!((0.2..0.3).contains(&app.progress()));
+    }
+
+    #[test]
+    fn the_break_shows_the_tip_its_notification_had() {
+        let completion = config::PerMode { work: Some(CompletionBehavior::Ask), ..config::PerMode::default() };
+        let mut app = app_from(Config { completion, tip_in_notification: true, ..Config::default() });
+        for _ in 0..5 {
+            let _ = app.update(PomodoroMessage::Start);
+            finish(&mut app);
+            let offered = app.tip;
+            assert!(offered.is_some() && app.tip_ready);
+            let _ = app.update(PomodoroMessage::StartNext);
+            assert!(app.mode.is_break());
+            assert_eq!((app.tip, app.tip_ready), (offered, false));
+            let _ = app.update(PomodoroMessage::SwitchMode(Mode::Work));
+        }
+    }
+
+    #[test]
+    fn ask_offers_each_next_step() {
+        let completion = config::PerMode { work: Some(CompletionBehavior::Ask), ..config::PerMode::default() };
+        let mut app = app_from(Config { completion, ..Config::default() });
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        assert_eq!(step(&app), (Mode::Work, State::Completed { finished: Mode::Work }, 1));
+
+        let _ = app.update(PomodoroMessage::StartMode(Mode::Work));
+        assert_eq!(step(&app), (Mode::Work, State::Running, 1));
+        finish(&mut app);
+        let _ = app.update(PomodoroMessage::StartNext);
+        assert_eq!(step(&app), (Mode::ShortBreak, State::Running, 2));
+
+        let _ = app.update(PomodoroMessage::SwitchMode(Mode::Work));
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        let _ = app.update(PomodoroMessage::Done);
+        assert_eq!(step(&app), (Mode::Work, State::Idle, 3));
+    }
+
+    #[test]
+    fn per_mode_completion_overrides_auto_start() {
+        let completion = config::PerMode { short_break: Some(CompletionBehavior::Stop), ..config::PerMode::default() };
+        let mut app = app_from(Config { auto_start_breaks: true, auto_start_work: true, completion, ..Config::default() });
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        assert_eq!(step(&app), (Mode::ShortBreak, State::Running, 1));
+        finish(&mut app);
+        assert_eq!(step(&app), (Mode::ShortBreak, State::Completed { finished: Mode::ShortBreak }, 1));
+    }
+
+    #[test]
+    fn long_break_after_cuts_the_set_short() {
+        let mut app = app_from(Config { auto_start_breaks: true, auto_start_work: true, long_break_after: 2, block_work_until_long_break: true, ..Config::default() });
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        finish(&mut app);
+        finish(&mut app);
+        assert_eq!(step(&app), (Mode::LongBreak, State::Running, 2));
+
+        // Work stays blocked until the long break is over
+        let _ = app.update(PomodoroMessage::SwitchMode(Mode::Work));
+        let _ = app.update(PomodoroMessage::Start);
+        assert_eq!(step(&app), (Mode::Work, State::Idle, 2));
+
+        let _ = app.update(PomodoroMessage::SwitchMode(Mode::LongBreak));
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        assert_eq!(step(&app), (Mode::Work, State::Running, 2));
+        assert_eq!(app.daily.counters.work_since_long_break, 0);
+    }
+
+    #[test]
+    fn a_one_second_session_completes_once_and_alerts_once() {
+        let dir = std::env::temp_dir().join(format!("rusty-pomodoro-one-second-{}", std::process::id()));
+        let persistence = Persistence::Disk { config_file: dir.join("config.toml"), data_dir: dir.clone() };
+        let mut app = Pomodoro::new(flags(Config { sound_theme: SoundTheme::Silent, ..Config::default() }, persistence)).0;
+        let _ = app.update(PomodoroMessage::EditTime);
+        let _ = app.update(PomodoroMessage::SetTimeInput(String::from("0:01")));
+        let _ = app.update(PomodoroMessage::SubmitTime);
+        let _ = app.update(PomodoroMessage::Start);
+
+        // 00:00 is shown for the last moments, but the session is not over until it is at zero
+        app.last_tick -= Duration::from_millis(400);
+        let _ = app.update(PomodoroMessage::Tick);
+        assert_eq!((app.countdown().as_str(), app.state), ("00:00", State::Running));
+        finish(&mut app);
+        for _ in 0..3 {
+            let _ = app.update(PomodoroMessage::Tick);
+        }
+        assert_eq!(app.state, State::Completed { finished: Mode::Work });
+        assert_eq!((app.completed_work_sessions, app.daily.counters.pomodoros_completed), (1, 1));
+        assert_eq!(session_log::read_all(&dir).unwrap().len(), 1);
+        let alerted = app.last_alert.expect("the completion alerted");
+
+        // Another one right after is counted and logged, but doesn't ring again
+        let _ = app.update(PomodoroMessage::SwitchMode(Mode::Work));
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        assert_eq!(session_log::read_all(&dir).unwrap().len(), 2);
+        assert_eq!(app.last_alert, Some(alerted));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn removing_sessions_takes_off_what_they_added_despite_rounding() {
+        let dir = std::env::temp_dir().join(format!("rusty-pomodoro-unrecord-{}", std::process::id()));
+        let persistence = Persistence::Disk { config_file: dir.join("config.toml"), data_dir: dir.clone() };
+        let config = Config { sound_theme: SoundTheme::Silent, log_rounding_minutes: 5, session_notes: false, ..Config::default() };
+        let mut app = Pomodoro::new(flags(config, persistence)).0;
+        app.tasks.tasks.push(tasks::Task { name: String::from("Taxes"), estimate: 2, completed: 0 });
+        app.tasks.current = Some(0);
+
+        // Two minutes, logged as zero
+        let _ = app.update(PomodoroMessage::Start);
+        app.timer -= Duration::from_secs(120);
+        let _ = app.update(PomodoroMessage::Reset);
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        assert_eq!((app.daily.counters.work_secs, app.tasks.tasks[0].completed), (app.durations.work.as_secs() + 120, 1));
+
+        for left in [120, 0] {
+            let _ = app.update(PomodoroMessage::RemoveLastSession);
+            let _ = app.update(PomodoroMessage::ConfirmRemoveLast(true));
+            assert_eq!((app.daily.counters.work_secs, app.tasks.tasks[0].completed), (left, 0));
+        }
+        assert_eq!(app.daily.counters.pomodoros_started, 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn start_leaves_a_running_session_alone_unless_told_to_restart() {
+        for (restarts, expected_left) in [(false, Duration::from_secs(20 * 60)), (true, Duration::from_secs(25 * 60))] {
+            let mut app = app_from(Config { start_while_running_restarts: restarts, ..Config::default() });
+            let _ = app.update(PomodoroMessage::Start);
+            app.timer = Duration::from_secs(20 * 60);
+            let _ = app.update(PomodoroMessage::Start);
+            assert_eq!(app.state, State::Running);
+            assert_eq!(app.timer, expected_left);
+        }
+    }
+
+    #[test]
+    fn a_paused_session_comes_back_to_the_millisecond() {
+        let mut app = app_from(Config::default());
+        let _ = app.update(PomodoroMessage::Start);
+        app.timer = Duration::from_millis(12 * 60_000 + 34_567);
+        app.last_tick = Instant::now();
+        let _ = app.update(PomodoroMessage::Pause);
+        // Pausing counts the moments since the last tick instead of rounding to it
+        assert!(Duration::from_millis(12 * 60_000 + 34_500) < app.timer && app.timer <= Duration::from_millis(12 * 60_000 + 34_567));
+
+        app.timer = Duration::from_millis(12 * 60_000 + 34_567);
+        let saved = toml::to_string(&app.snapshot()).unwrap();
+        let mut reloaded = app_from(Config::default());
+        reloaded.restore(toml::from_str(&saved).unwrap());
+        assert_eq!(reloaded.timer, Duration::from_millis(12 * 60_000 + 34_567));
+        assert_eq!(reloaded.session_total, app.session_total);
+        assert!(matches!(reloaded.state, State::Paused(_)));
+    }
+
+    #[test]
+    fn without_a_config_directory_everything_stays_in_memory() {
+        let persistence = Persistence::from_dirs(None);
+        assert_eq!(persistence, Persistence::Memory);
+        let config = Config { sound_theme: SoundTheme::Silent, ..Config::default() };
+        let mut app = Pomodoro::new(flags(config, persistence)).0;
+        assert_eq!(app.error.as_deref(), Persistence::Memory.warning());
+        assert_eq!(app.screen, Screen::Timer);
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        assert_eq!(app.daily.counters.pomodoros_completed, 1);
+        let _ = app.update(PomodoroMessage::ShowScreen(Screen::Settings));
+        let _ = app.update(PomodoroMessage::ShowScreen(Screen::Timer));
+        assert!(app.export_calendar().is_err());
+    }
+
+    #[test]
+    fn fuzzy_labels_get_vaguer_the_further_off_the_end_is() {
+        let label = |secs| fuzzy_label(Duration::from_secs(secs));
+        assert_eq!(label(0), "almost done");
+        assert_eq!(label(59), "almost done");
+        assert_eq!(label(60), "a few minutes left");
+        assert_eq!(label(299), "a few minutes left");
+        assert_eq!(label(300), "about 5 min left");
+        assert_eq!(label(7 * 60 + 29), "about 5 min left");
+        assert_eq!(label(7 * 60 + 30), "about 10 min left");
+        assert_eq!(label(20 * 60), "about 20 min left");
+        assert_eq!(label(54 * 60 + 59), "about 55 min left");
+        assert_eq!(label(55 * 60), "about an hour left");
+        assert_eq!(label(90 * 60), "about 2 hours left");
+        assert_eq!(label(3 * 3600 + 1200), "about 3 hours left");
+    }
+
+    #[test]
+    fn typed_lengths_are_minutes_or_minutes_and_seconds() {
+        assert_eq!(parse_time("25"), Some(Duration::from_secs(25 * 60)));
+        assert_eq!(parse_time(" 12:30 "), Some(Duration::from_secs(12 * 60 + 30)));
+        assert_eq!(parse_time("0:45"), Some(Duration::from_secs(45)));
+        assert_eq!(parse_time("1440"), Some(config::MAX_DURATION));
+        for invalid in ["", "0", "0:00", "1441", "5:60", "5:", ":30", "1:2:3", "-5", "ten", "5.5", "307445734561825860:59"] {
+            assert_eq!(parse_time(invalid), None, "{:?}", invalid);
+        }
+    }
+
+    fn reset_halfway(behavior: ResetBehavior) -> Pomodoro {
+        let mut app = app_from(Config { reset_behavior: behavior, ..Config::default() });
+        let _ = app.update(PomodoroMessage::Start);
+        app.timer -= Duration::from_secs(10 * 60);
+        let _ = app.update(PomodoroMessage::Reset);
+        assert_eq!(app.state, State::Idle);
+        app
+    }
+
+    #[test]
+    fn reset_refills_by_default() {
+        let mut app = reset_halfway(ResetBehavior::RefillAndStop);
+        assert_eq!(app.timer, app.durations.work);
+        let _ = app.update(PomodoroMessage::Start);
+        assert_eq!(app.session_total, app.durations.work);
+    }
+
+    #[test]
+    fn stop_only_reset_keeps_the_time_left_for_the_next_start() {
+        let mut app = reset_halfway(ResetBehavior::StopOnly);
+        let left = app.durations.work - Duration::from_secs(10 * 60);
+        assert_eq!(app.timer, left);
+        let _ = app.update(PomodoroMessage::Start);
+        assert_eq!(app.state, State::Running);
+        assert_eq!(app.timer, left);
+
+        // Switching modes in between puts the full length back
+        let mut app = reset_halfway(ResetBehavior::StopOnly);
+        let _ = app.update(PomodoroMessage::SwitchMode(Mode::ShortBreak));
+        let _ = app.update(PomodoroMessage::SwitchMode(Mode::Work));
+        let _ = app.update(PomodoroMessage::Start);
+        assert_eq!(app.timer, app.durations.work);
+    }
+
+    #[test]
+    fn clear_reset_goes_to_zero_and_starts_a_full_session() {
+        let mut app = reset_halfway(ResetBehavior::Clear);
+        assert_eq!(app.timer, Duration::ZERO);
+        assert_eq!(app.countdown(), "00:00");
+        let _ = app.update(PomodoroMessage::Start);
+        assert_eq!(app.timer, app.durations.work);
+    }
+
+    #[test]
+    fn finishing_is_not_the_same_as_never_starting() {
+        let mut app = app_from(Config::default());
+        assert_eq!(app.state, State::Idle);
+        let _ = app.update(PomodoroMessage::Start);
+        finish(&mut app);
+        assert_eq!(app.state, State::Completed { finished: Mode::Work });
+        let _ = app.update(PomodoroMessage::Reset);
+        assert_eq!(app.state, State::Idle);
+        assert_eq!(app.timer, app.durations.work);
+    }
+}
@@ -0,0 +1,58 @@
+use iced::futures::{channel::oneshot, Future};
+use notify_rust::Notification;
+use std::thread;
+
+/// Shows a desktop notification. Sending can block on the notification daemon, so it happens on
+/// a separate thread and failures are only reported.
+pub fn send(summary: impl Into<String>, body: impl Into<String>) {
+    let (summary, body) = (summary.into(), body.into());
+    thread::spawn(move || show(&summary, &body));
+}
+
+/// Like `send`, with a button for each `(id, label)` of `actions`. Resolves to the id of the
+/// button clicked, or "default" for the notification itself, and to `None` once it's closed
+/// otherwise. Where buttons aren't supported, which is anywhere but a notification daemon
+/// advertising them on Linux and the BSDs, it's shown plain and resolves to `None` right away.
+pub fn send_with_actions(summary: impl Into<String>, body: impl Into<String>, actions: Vec<(&'static str, String)>) -> impl Future<Output = Option<String>> {
+    let (summary, body) = (summary.into(), body.into());
+    let (clicked, action) = oneshot::channel();
+    thread::spawn(move || match show_with_actions(&summary, &body, &actions) {
+        Some(Ok(id)) => {
+            let _ = clicked.send(id);
+        }
+        Some(Err(err)) => eprintln!("Could not show notification: {}", err),
+        None => show(&summary, &body),
+    });
+    async move { action.await.ok().filter(|id| id != "__closed") }
+}
+
+/// Shows the notification and waits for it to be acted on. `None` without support for buttons.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn show_with_actions(summary: &str, body: &str, actions: &[(&'static str, String)]) -> Option<notify_rust::error::Result<String>> {
+    if !notify_rust::get_capabilities().is_ok_and(|capabilities| capabilities.iter().any(|capability| capability == "actions")) {
+        return None;
+    }
+    let mut notification = Notification::new();
+    notification.appname("Rusty Pomodoro").summary(summary).body(body);
+    for (id, label) in actions {
+        notification.action(id, label);
+    }
+    Some(notification.show().map(|handle| {
+        let mut clicked = String::new();
+        handle.wait_for_action(|id| clicked = id.to_string());
+        clicked
+    }))
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn show_with_actions(_summary: &str, _body: &str, _actions: &[(&'static str, String)]) -> Option<notify_rust::error::Result<String>> {
+    None
+}
+
+/// Like `send`, but waits for the notification daemon. For when the app is about to exit and a
+/// thread of its own might not get to finish.
+pub fn show(summary: &str, body: &str) {
+    if let Err(err) = Notification::new().appname("Rusty Pomodoro").summary(summary).body(body).show() {
+        eprintln!("Could not show notification: {}", err);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::technique::Technique;
+use std::env;
+
+/// Session lengths given on the command line or in the environment, on top of the configured
+/// technique. Command line flags win over environment variables, which win over the config file.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DurationOverrides {
+    pub work_minutes: Option<u64>,
+    pub short_break_minutes: Option<u64>,
+    pub long_break_minutes: Option<u64>,
+}
+
+impl DurationOverrides {
+    /// Reads `POMODORO_WORK_MINUTES`, `POMODORO_SHORT_MINUTES` and `POMODORO_LONG_MINUTES`.
+    pub fn from_env() -> DurationOverrides {
+        let read = |name: &str| env::var(name).ok().and_then(|value| minutes(name, &value));
+        DurationOverrides {
+            work_minutes: read("POMODORO_WORK_MINUTES"),
+            short_break_minutes: read("POMODORO_SHORT_MINUTES"),
+            long_break_minutes: read("POMODORO_LONG_MINUTES"),
+        }
+    }
+
+    /// Parses `--work`, `--short` and `--long`, each followed by a number of minutes
+    /// either as the next argument or after an `=`.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> DurationOverrides {
+        let mut overrides = DurationOverrides::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let slot = match flag.as_str() {
+                "--work" => &mut overrides.work_minutes,
+                "--short" => &mut overrides.short_break_minutes,
+                "--long" => &mut overrides.long_break_minutes,
+                _ => {
+                    eprintln!("Ignoring unknown argument {:?}", flag);
+                    continue;
+                }
+            };
+            match inline.or_else(|| args.next()) {
+                Some(value) => *slot = minutes(&flag, &value).or(*slot),
+                None => eprintln!("Ignoring {} without a number of minutes", flag),
+            }
+        }
+        overrides
+    }
+
+    /// Fills in anything not set here from `fallback`.
+    pub fn or(self, fallback: DurationOverrides) -> DurationOverrides {
+        DurationOverrides {
+            work_minutes: self.work_minutes.or(fallback.work_minutes),
+            short_break_minutes: self.short_break_minutes.or(fallback.short_break_minutes),
+            long_break_minutes: self.long_break_minutes.or(fallback.long_break_minutes),
+        }
+    }
+
+    pub fn apply(&self, technique: &mut Technique) {
+        if let Some(minutes) = self.work_minutes {
+            technique.work_minutes = minutes;
+        }
+        if let Some(minutes) = self.short_break_minutes {
+            technique.short_break_minutes = minutes;
+        }
+        if let Some(minutes) = self.long_break_minutes {
+            technique.long_break_minutes = minutes;
+        }
+    }
+}
+
+/// A positive whole number of minutes, with a warning for anything else.
+fn minutes(source: &str, value: &str) -> Option<u64> {
+    match value.trim().parse() {
+        Ok(0) | Err(_) => {
+            eprintln!("Ignoring {}={:?}: expected a positive number of minutes", source, value);
+            None
+        }
+        Ok(minutes) => Some(minutes),
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{Mode, PauseReason, Pomodoro, PomodoroMessage, Screen, State};
+use iced::{widget::{text_input, Button, Column, Container, Row, Scrollable, Text, TextInput}, Alignment, Element, Length};
+
+/// Id of the filter box, so it can be focused when the palette opens.
+pub fn input_id() -> text_input::Id {
+    text_input::Id::new("palette")
+}
+
+/// True if every character of `query` appears in `label` in order, ignoring case.
+pub fn fuzzy_match(query: &str, label: &str) -> bool {
+    let mut label = label.chars().flat_map(char::to_lowercase);
+    query.chars().flat_map(char::to_lowercase).filter(|c| !c.is_whitespace()).all(|c| label.any(|l| l == c))
+}
+
+impl Pomodoro {
+    /// Every action that makes sense right now, with its label.
+    fn actions(&self) -> Vec<(String, PomodoroMessage)> {
+        let mut actions = Vec::new();
+        match self.state {
+            State::Idle => actions.push((String::from("Start"), PomodoroMessage::Start)),
+            State::Running => {
+                actions.push((String::from("Pause"), PomodoroMessage::Pause));
+                actions.push((String::from("Pause for a meeting"), PomodoroMessage::PauseFor(PauseReason::Meeting)));
+            }
+            State::Paused(_) => actions.push((String::from("Resume"), PomodoroMessage::Resume)),
+            State::Resuming { .. } => actions.push((String::from("Resume now"), PomodoroMessage::Resume)),
+            State::Overtime | State::Held | State::Completed { .. } => {
+                let next = self.next_mode(self.mode);
+                actions.push((format!("Start {}", self.config.label_for(next)), PomodoroMessage::StartNext));
+            }
+        }
+        if self.state != State::Idle {
+            actions.push((String::from("Restart"), PomodoroMessage::Restart));
+        }
+        if matches!(self.state, State::Running | State::Paused(_) | State::Resuming { .. }) {
+            actions.push((String::from("Skip"), PomodoroMessage::Skip));
+        }
+        if self.config.time_bank_max_minutes > 0 && self.daily.counters.banked_secs > 0 && !matches!(self.state, State::Overtime | State::Held | State::Completed { .. }) {
+            actions.push((String::from("Use time bank"), PomodoroMessage::UseBank));
+        }
+        actions.push((String::from("Reset"), PomodoroMessage::Reset));
+        for mode in [Mode::Work, Mode::ShortBreak, Mode::LongBreak] {
+            actions.push((format!("Switch to {}", self.config.label_for(mode)), PomodoroMessage::SwitchMode(mode)));
+        }
+        for name in self.config.routines.keys() {
+            actions.push((format!("Start routine {}", name), PomodoroMessage::StartRoutine(name.clone())));
+        }
+        actions.extend([
+            (String::from("Open settings"), PomodoroMessage::ShowScreen(Screen::Settings)),
+            (String::from("Show HUD"), PomodoroMessage::ShowScreen(Screen::Hud)),
+            (String::from("Manage tasks"), PomodoroMessage::ShowScreen(Screen::Tasks)),
+            (String::from("Show pomodoro heatmap"), PomodoroMessage::ShowScreen(Screen::Heatmap)),
+            (String::from("Run 5s test session"), PomodoroMessage::StartTestSession),
+            (String::from("Export today as .ics"), PomodoroMessage::ExportCalendar),
+            (String::from("Toggle drift debug line"), PomodoroMessage::ToggleDebug),
+            (String::from(if self.config.focus_mode { "Turn focus mode off" } else { "Turn focus mode on" }), PomodoroMessage::ToggleFocusMode),
+        ]);
+        actions
+    }
+
+    pub(crate) fn palette_view(&self) -> Element<'_, PomodoroMessage> {
+        let matches: Vec<_> = self.actions().into_iter().filter(|(label, _)| fuzzy_match(&self.palette_query, label)).collect();
+        let mut input = TextInput::new("Type to search actions", &self.palette_query).id(input_id()).on_input(PomodoroMessage::SetPaletteQuery);
+        if let Some((_, first)) = matches.first() {
+            input = input.on_submit(PomodoroMessage::RunAction(Box::new(first.clone())));
+        }
+        let list = matches.into_iter().fold(Column::new().spacing(2), |col, (label, message)| {
+            col.push(Button::new(Text::new(label)).width(Length::Fill).on_press(PomodoroMessage::RunAction(Box::new(message)).pressed()))
+        });
+        let header = Row::new().spacing(10).align_items(Alignment::Center)
+            .push(input)
+            .push(Button::new("Close").on_press(PomodoroMessage::ClosePalette.pressed()));
+        let col = Column::new().spacing(10).padding(20).push(header).push(Scrollable::new(list).height(Length::Fill));
+        Container::new(col).width(Length::Fill).height(Length::Fill).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_match_letters_in_order_ignoring_case_and_spaces() {
+        for (query, label) in [("", "Start"), ("start", "Start"), ("sb", "Short break"), ("SHORT B", "Short break"), ("psm", "Pause for a meeting"), ("ÉT", "Été")] {
+            assert!(fuzzy_match(query, label), "{:?} in {:?}", query, label);
+        }
+        for (query, label) in [("bs", "Short break"), ("starts", "Start"), ("x", "Reset"), ("rr", "Reset")] {
+            assert!(!fuzzy_match(query, label), "{:?} in {:?}", query, label);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::{sync::mpsc, thread};
+
+/// Well-known name the app takes on the session bus.
+pub const BUS_NAME: &str = "io.github.shidoxo.RustyPomodoro";
+/// Object the timer interface is served at.
+pub const OBJECT_PATH: &str = "/io/github/shidoxo/RustyPomodoro";
+
+/// What a panel extension shows, as published on the bus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Status {
+    /// `None` while roulette hides the session's length.
+    pub remaining_secs: Option<u64>,
+    /// A mode key, like "work" or "short_break".
+    pub mode: &'static str,
+    /// "idle", "running", "paused" and so on, the same names the HTTP endpoint uses.
+    pub state: &'static str,
+}
+
+struct Timer {
+    status: Status,
+}
+
+/// Read-only properties; watchers get `org.freedesktop.DBus.Properties.PropertiesChanged` when
+/// any of them changes.
+#[zbus::interface(name = "io.github.shidoxo.RustyPomodoro.Timer")]
+impl Timer {
+    /// -1 while the session's length is hidden.
+    #[zbus(property)]
+    fn remaining_seconds(&self) -> i64 {
+        self.status.remaining_secs.map_or(-1, |secs| secs.try_into().unwrap_or(i64::MAX))
+    }
+
+    #[zbus(property)]
+    fn mode(&self) -> &str {
+        self.status.mode
+    }
+
+    #[zbus(property)]
+    fn state(&self) -> &str {
+        self.status.state
+    }
+}
+
+/// Sends status changes to the helper thread that owns the bus connection. The default one
+/// has no thread and publishes nothing.
+#[derive(Default)]
+pub struct Publisher {
+    last: Option<Status>,
+    updates: Option<mpsc::Sender<Status>>,
+}
+
+impl Publisher {
+    /// Connects to the session bus on a helper thread. Without a bus, publishing does nothing.
+    pub fn start() -> Publisher {
+        let (updates, received) = mpsc::channel::<Status>();
+        thread::spawn(move || {
+            if let Err(err) = serve(received) {
+                eprintln!("Could not publish the timer on D-Bus: {}", err);
+            }
+        });
+        Publisher { last: None, updates: Some(updates) }
+    }
+
+    /// Publishes `status` if it differs from what was published last.
+    pub fn publish(&mut self, status: Status) {
+        if self.last.as_ref() == Some(&status) {
+            return;
+        }
+        self.last = Some(status.clone());
+        if let Some(updates) = &self.updates {
+            if updates.send(status).is_err() {
+                self.updates = None;
+            }
+        }
+    }
+}
+
+fn serve(received: mpsc::Receiver<Status>) -> zbus::Result<()> {
+    let first = match received.recv() {
+        Ok(status) => status,
+        Err(_) => return Ok(()),
+    };
+    let connection = zbus::blocking::connection::Builder::session()?.name(BUS_NAME)?.serve_at(OBJECT_PATH, Timer { status: first })?.build()?;
+    let timer = connection.object_server().interface::<_, Timer>(OBJECT_PATH)?;
+    for status in received {
+        let previous = std::mem::replace(&mut timer.get_mut().status, status.clone());
+        let context = timer.signal_context();
+        let interface = timer.get();
+        zbus::block_on(async {
+            if previous.remaining_secs != status.remaining_secs {
+                interface.remaining_seconds_changed(context).await?;
+            }
+            if previous.mode != status.mode {
+                interface.mode_changed(context).await?;
+            }
+            if previous.state != status.state {
+                interface.state_changed(context).await?;
+            }
+            zbus::Result::Ok(())
+        })?;
+    }
+    Ok(())
+}
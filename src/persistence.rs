@@ -0,0 +1,78 @@
+use directories::ProjectDirs;
+use std::{fs, io, path::{Path, PathBuf}};
+
+/// Where settings and state are kept between runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Persistence {
+    /// The config file and the data directory for snapshots, the session log and stats.
+    Disk { config_file: PathBuf, data_dir: PathBuf },
+    /// Nothing is read or written, for when the platform directories can't be resolved, e.g.
+    /// without a home directory in a sandbox. Everything still works until the app quits.
+    Memory,
+}
+
+impl Persistence {
+    /// Uses the platform directories (`~/.config/rusty-pomodoro/` and friends on Linux).
+    pub fn resolve() -> Persistence {
+        Persistence::from_dirs(ProjectDirs::from("", "", "rusty-pomodoro"))
+    }
+
+    pub fn from_dirs(dirs: Option<ProjectDirs>) -> Persistence {
+        match dirs {
+            Some(dirs) => Persistence::Disk { config_file: dirs.config_dir().join("config.toml"), data_dir: dirs.data_dir().to_path_buf() },
+            None => Persistence::Memory,
+        }
+    }
+
+    pub fn config_file(&self) -> Option<&Path> {
+        match self {
+            Persistence::Disk { config_file, .. } => Some(config_file),
+            Persistence::Memory => None,
+        }
+    }
+
+    pub fn data_dir(&self) -> Option<&Path> {
+        match self {
+            Persistence::Disk { data_dir, .. } => Some(data_dir),
+            Persistence::Memory => None,
+        }
+    }
+
+    /// Shown once at startup, so that saves being skipped later doesn't come as a surprise.
+    pub fn warning(&self) -> Option<&'static str> {
+        match self {
+            Persistence::Disk { .. } => None,
+            Persistence::Memory => Some("No config directory was found, so settings, sessions and stats won't be saved"),
+        }
+    }
+}
+
+/// File in the data directory holding the process id of the app using it.
+pub const IN_USE_FILE: &str = "running.pid";
+
+/// Marks a data directory as in use by the running app for as long as this lives, so that
+/// command-line tools writing to the same files can tell.
+pub struct InUse(PathBuf);
+
+impl InUse {
+    pub fn mark(data_dir: &Path) -> io::Result<InUse> {
+        fs::create_dir_all(data_dir)?;
+        let path = data_dir.join(IN_USE_FILE);
+        fs::write(&path, std::process::id().to_string())?;
+        Ok(InUse(path))
+    }
+}
+
+impl Drop for InUse {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// The process id of an app that has `data_dir` marked as in use. Only Linux can tell whether
+/// that process is still around; elsewhere a mark left behind by a crash counts too.
+pub fn in_use(data_dir: &Path) -> Option<u32> {
+    let pid: u32 = fs::read_to_string(data_dir.join(IN_USE_FILE)).ok()?.trim().parse().ok()?;
+    let alive = !cfg!(target_os = "linux") || Path::new("/proc").join(pid.to_string()).exists();
+    (alive && pid != std::process::id()).then_some(pid)
+}
@@ -0,0 +1,30 @@
+use iced::{mouse, widget::canvas::{self, Cache, Geometry, Path}, Color, Point, Rectangle, Renderer, Theme};
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+/// The seconds of the current minute as a ring of 60 dots, lit clockwise from the top.
+/// Drawing goes through `cache`, which the owner clears whenever the second changes.
+pub struct SecondsRing<'a> {
+    pub seconds: u64,
+    pub cache: &'a Cache,
+}
+
+impl<'a, Message> canvas::Program<Message> for SecondsRing<'a> {
+    type State = ();
+
+    fn draw(&self, _state: &(), renderer: &Renderer, theme: &Theme, bounds: Rectangle, _cursor: mouse::Cursor) -> Vec<Geometry> {
+        let ring = self.cache.draw(renderer, bounds.size(), |frame| {
+            let palette = theme.palette();
+            let dimmed = Color { a: 0.15, ..palette.text };
+            let center = frame.center();
+            let dot_radius = 2.5;
+            let radius = frame.width().min(frame.height()) / 2.0 - dot_radius;
+            let lit = self.seconds % 60;
+            for dot in 0..60 {
+                let angle = dot as f32 / 60.0 * TAU - FRAC_PI_2;
+                let position = Point::new(center.x + radius * angle.cos(), center.y + radius * angle.sin());
+                frame.fill(&Path::circle(position, dot_radius), if dot < lit { palette.primary } else { dimmed });
+            }
+        });
+        vec![ring]
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{config::MAX_DURATION, Mode};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// One session of a routine.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    pub mode: Mode,
+    pub minutes: u64,
+}
+
+impl Step {
+    pub fn length(self) -> Duration {
+        Duration::from_secs(self.minutes.saturating_mul(60))
+    }
+}
+
+/// Most times a routine's steps can run back to back.
+pub const MAX_REPEAT: u32 = 100;
+
+/// A named chain of sessions, declared in the config, that runs from start to finish on its
+/// own once picked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Routine {
+    pub steps: Vec<Step>,
+    /// How many times `steps` run back to back.
+    #[serde(default = "once")]
+    pub repeat: u32,
+}
+
+fn once() -> u32 {
+    1
+}
+
+impl Routine {
+    /// Every session of the routine in order. Steps of zero minutes or longer than
+    /// `MAX_DURATION` are left out, and a `repeat` over `MAX_REPEAT` leaves nothing to run.
+    pub fn expand(&self) -> Vec<Step> {
+        if self.repeat > MAX_REPEAT {
+            return Vec::new();
+        }
+        let steps = self.steps.iter().copied().filter(|step| step.minutes > 0 && step.length() <= MAX_DURATION);
+        steps.collect::<Vec<_>>().repeat(self.repeat as usize)
+    }
+}
+
+/// A routine being worked through.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub name: String,
+    pub steps: Vec<Step>,
+    /// Index into `steps` of the session running now.
+    pub current: usize,
+}
+
+impl Progress {
+    /// Moves on to the next session, if there is one left.
+    pub fn advance(&mut self) -> Option<Step> {
+        self.current += 1;
+        self.steps.get(self.current).copied()
+    }
+
+    /// The session after the current one, if there is one left.
+    pub fn next(&self) -> Option<Step> {
+        self.steps.get(self.current + 1).copied()
+    }
+
+    /// Planned time of the sessions after the current one.
+    pub fn after_current(&self) -> Duration {
+        self.steps.iter().skip(self.current + 1).map(|step| step.length()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_routine_expands_into_its_steps_repeated() {
+        let work = Step { mode: Mode::Work, minutes: 52 };
+        let rest = Step { mode: Mode::ShortBreak, minutes: 17 };
+        let routine = Routine { steps: vec![work, Step { mode: Mode::LongBreak, minutes: 0 }, rest], repeat: 2 };
+        let steps = routine.expand();
+        assert_eq!(steps, vec![work, rest, work, rest]);
+
+        let mut progress = Progress { name: String::from("Morning"), steps, current: 0 };
+        assert_eq!(progress.after_current(), Duration::from_secs((17 + 52 + 17) * 60));
+        assert_eq!(progress.advance(), Some(rest));
+        progress.current = 3;
+        assert_eq!(progress.advance(), None);
+
+        let huge = Routine { steps: vec![work, Step { mode: Mode::Work, minutes: u64::MAX / 2 }], repeat: MAX_REPEAT + 1 };
+        assert_eq!(huge.expand(), Vec::new());
+        assert_eq!(Routine { repeat: 1, ..huge }.expand(), vec![work]);
+    }
+}
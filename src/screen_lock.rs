@@ -0,0 +1,55 @@
+use crate::PomodoroMessage;
+use iced::{futures::{channel::mpsc, SinkExt, StreamExt}, subscription, Subscription};
+use std::{io::{BufRead, BufReader}, process::{Command, Stdio}, thread};
+
+/// Reports `PomodoroMessage::ScreenLocked` whenever the session is locked or unlocked.
+///
+/// Listens for the `ActiveChanged` signal of `org.freedesktop.ScreenSaver` on the session bus
+/// through `dbus-monitor`, which GNOME, KDE and most other Linux desktops send on lock. Other
+/// platforms never report anything.
+pub fn events() -> Subscription<PomodoroMessage> {
+    struct ScreenLock;
+
+    subscription::channel(std::any::TypeId::of::<ScreenLock>(), 1, |mut output| async move {
+        let (sender, mut events) = mpsc::unbounded();
+        if cfg!(all(unix, not(target_os = "macos"))) {
+            thread::spawn(move || {
+                let monitor = Command::new("dbus-monitor")
+                    .args(["--session", "type='signal',interface='org.freedesktop.ScreenSaver',member='ActiveChanged'"])
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .spawn();
+                let mut monitor = match monitor {
+                    Ok(monitor) => monitor,
+                    Err(err) => {
+                        eprintln!("Could not watch for screen locks: {}", err);
+                        return;
+                    }
+                };
+                let Some(stdout) = monitor.stdout.take() else {
+                    return;
+                };
+                // The signal's header line is followed by a line holding its one boolean argument
+                let mut in_signal = false;
+                for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                    let line = line.trim();
+                    if line.starts_with("signal ") {
+                        in_signal = line.contains("member=ActiveChanged");
+                    } else if in_signal {
+                        if let Some(locked) = line.strip_prefix("boolean ") {
+                            in_signal = false;
+                            if sender.unbounded_send(locked == "true").is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+                let _ = monitor.kill();
+            });
+        }
+        while let Some(locked) = events.next().await {
+            let _ = output.send(PomodoroMessage::ScreenLocked(locked)).await;
+        }
+        iced::futures::future::pending().await
+    })
+}
@@ -0,0 +1,553 @@
+use crate::Mode;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs::{self, OpenOptions}, io::{self, BufRead, BufReader, Write}, path::{Path, PathBuf}, time::{Duration, SystemTime}};
+
+const HEADER: &str = "started_at,ended_at,mode,planned_secs,actual_secs,completed,tag,note,paused";
+
+/// What a row of the log is, kept in its `tag` column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordKind {
+    Session,
+    /// One of the short test runs started from settings, so they can be filtered out.
+    Test,
+    /// Time away from the desk while a session was paused, from `log_away_gaps`. Its actual
+    /// duration is the time away and it never counts as completed.
+    Gap,
+    /// Extra minutes asked for from a completion notification. They add to the time worked but
+    /// not to the sessions counted.
+    Extension,
+}
+
+impl RecordKind {
+    fn tag(self) -> &'static str {
+        match self {
+            RecordKind::Session => "",
+            RecordKind::Test => "test",
+            RecordKind::Gap => "gap",
+            RecordKind::Extension => "extension",
+        }
+    }
+
+    fn from_tag(tag: &str) -> RecordKind {
+        match tag {
+            "test" => RecordKind::Test,
+            "gap" => RecordKind::Gap,
+            "extension" => RecordKind::Extension,
+            _ => RecordKind::Session,
+        }
+    }
+}
+
+/// One finished or abandoned session, or a gap in one, as written to `sessions.csv`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionRecord {
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+    pub mode: Mode,
+    pub planned: Duration,
+    pub actual: Duration,
+    pub completed: bool,
+    pub kind: RecordKind,
+    /// What the user jotted down after the session, if anything.
+    pub note: String,
+    /// Whether the session was paused at any point. Rows from before this column count as paused.
+    pub paused: bool,
+}
+
+/// Which of a record's durations `log_rounding_minutes` applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingTarget {
+    Planned,
+    Actual,
+    Both,
+}
+
+/// Rounding applied to durations on their way into the log. A zero step leaves them exact.
+#[derive(Debug, Clone, Copy)]
+pub struct Rounding {
+    pub step: Duration,
+    pub target: RoundingTarget,
+}
+
+/// When `sessions.csv` is moved aside and started over. Zero turns a limit off.
+#[derive(Debug, Clone, Copy)]
+pub struct Rotation {
+    pub max_bytes: u64,
+    /// Rotate once the oldest session in the file is this old.
+    pub max_age: Duration,
+    /// Delete rotated files last written longer ago than this.
+    pub keep: Duration,
+}
+
+impl SessionRecord {
+    fn csv_row(&self, rounding: Rounding) -> String {
+        let round = |duration, applies| if applies { round_to_nearest(duration, rounding.step) } else { duration };
+        let planned = round(self.planned, rounding.target != RoundingTarget::Actual);
+        let actual = round(self.actual, rounding.target != RoundingTarget::Planned);
+        format!(
+            "{},{},{},{},{},{},{},{},{}",
+            self.started_at.to_rfc3339(),
+            self.ended_at.to_rfc3339(),
+            self.mode.key(),
+            planned.as_secs(),
+            actual.as_secs(),
+            self.completed,
+            self.kind.tag(),
+            csv_field(&self.note),
+            self.paused,
+        )
+    }
+}
+
+/// Quotes a free-text field if it would otherwise break the row apart.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Appends a record to `sessions.csv` in `data_dir`, writing the header first if the file is new.
+pub fn append(data_dir: &Path, record: &SessionRecord, rounding: Rounding, rotation: Rotation) -> io::Result<()> {
+    fs::create_dir_all(data_dir)?;
+    let path = data_dir.join("sessions.csv");
+    // The record matters more than the housekeeping, so it goes in even when rotating fails
+    if let Err(err) = rotate(data_dir, &path, rotation, record.ended_at) {
+        eprintln!("Could not rotate {}: {}", path.display(), err);
+    }
+    migrate(&path)?;
+    let is_new = !path.exists();
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if is_new {
+        writeln!(file, "{}", HEADER)?;
+    }
+    writeln!(file, "{}", record.csv_row(rounding))
+}
+
+/// Brings a log started before the newer columns existed up to `HEADER`, so the file keeps one
+/// layout throughout for other programs reading it. Rotated logs are left as they are; reading
+/// them back already gives missing columns their defaults.
+fn migrate(path: &Path) -> io::Result<()> {
+    let mut header = String::new();
+    match fs::File::open(path) {
+        Ok(file) => BufReader::new(file).read_line(&mut header)?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    if header.trim_end() == HEADER {
+        return Ok(());
+    }
+    match upgraded(&fs::read_to_string(path)?) {
+        Some(contents) => fs::write(path, contents),
+        None => Ok(()),
+    }
+}
+
+/// `contents` with an older header swapped for `HEADER` and its rows given empty fields for the
+/// columns added since. `None` unless the header is one this app wrote before.
+fn upgraded(contents: &str) -> Option<String> {
+    let (header, rows) = contents.split_once('\n').unwrap_or((contents, ""));
+    let header = header.trim_end();
+    let older = header.starts_with("started_at,") && HEADER.strip_prefix(header).is_some_and(|added| added.starts_with(','));
+    if !older {
+        return None;
+    }
+    let columns = HEADER.split(',').count();
+    let mut upgraded = format!("{}\n", HEADER);
+    for row in rows_of(rows).into_iter().filter(|row| !row.trim().is_empty()) {
+        let missing = columns.saturating_sub(split_row(&row).len());
+        upgraded.push_str(&format!("{}{}\n", row, ",".repeat(missing)));
+    }
+    Some(upgraded)
+}
+
+/// Splits rows apart at line ends outside of quotes, so a note with a line break stays whole.
+fn rows_of(contents: &str) -> Vec<String> {
+    let mut rows: Vec<String> = Vec::new();
+    let mut open = false;
+    for line in contents.lines() {
+        match rows.last_mut().filter(|_| open) {
+            Some(row) => {
+                row.push('\n');
+                row.push_str(line);
+            }
+            None => rows.push(line.to_string()),
+        }
+        open ^= line.matches('"').count() % 2 == 1;
+    }
+    rows
+}
+
+/// Moves `path` aside as `sessions-<date>.csv` once it grows past `rotation`'s limits, then
+/// deletes rotated files that have outlived `rotation.keep`.
+fn rotate(data_dir: &Path, path: &Path, rotation: Rotation, now: DateTime<Local>) -> io::Result<()> {
+    let Ok(metadata) = fs::metadata(path) else {
+        return Ok(());
+    };
+    let too_big = rotation.max_bytes > 0 && metadata.len() >= rotation.max_bytes;
+    let too_old = !rotation.max_age.is_zero() && oldest_session(path)?.is_some_and(|started| (now - started).to_std().unwrap_or_default() >= rotation.max_age);
+    if too_big || too_old {
+        let date = now.date_naive();
+        let mut rotated = data_dir.join(format!("sessions-{}.csv", date));
+        for n in 2.. {
+            if !rotated.exists() {
+                break;
+            }
+            rotated = data_dir.join(format!("sessions-{}-{}.csv", date, n));
+        }
+        fs::rename(path, rotated)?;
+    }
+    if !rotation.keep.is_zero() {
+        for rotated in rotated_files(data_dir)? {
+            let age = fs::metadata(&rotated)?.modified()?.elapsed().unwrap_or_default();
+            if age > rotation.keep {
+                fs::remove_file(rotated)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether `name` is one `rotate` gives: `sessions-<date>.csv` or `sessions-<date>-<n>.csv`.
+fn is_rotated_name(name: &str) -> bool {
+    let Some(stem) = name.strip_prefix("sessions-").and_then(|name| name.strip_suffix(".csv")) else {
+        return false;
+    };
+    let (Some(date), Some(n)) = (stem.get(..10), stem.get(10..)) else {
+        return false;
+    };
+    let numbered = n.is_empty() || n.strip_prefix('-').is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+    numbered && NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok()
+}
+
+/// When the first session in the log started, read from the row under the header.
+fn oldest_session(path: &Path) -> io::Result<Option<DateTime<Local>>> {
+    let mut lines = BufReader::new(fs::File::open(path)?).lines().skip(1);
+    Ok(lines.next().transpose()?.as_deref().and_then(parse_row).map(|record| record.started_at))
+}
+
+/// Logs moved aside by `rotate`, oldest first.
+fn rotated_files(data_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<(SystemTime, PathBuf)> = Vec::new();
+    for entry in fs::read_dir(data_dir)? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        if is_rotated_name(name) {
+            files.push((fs::metadata(&path)?.modified()?, path));
+        }
+    }
+    files.sort();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+/// The newest row of `sessions.csv`, without touching the file.
+pub fn last(data_dir: &Path) -> io::Result<Option<SessionRecord>> {
+    Ok(read_log(data_dir)?.as_deref().and_then(without_last_row).map(|(_, record)| record))
+}
+
+/// Deletes the newest row of `sessions.csv` if it is still `expected`, as read by `last`, and
+/// tells whether it did. Anything logged in between is left alone. Rotated logs are too, so
+/// this finds nothing once the log has just been rotated.
+pub fn remove_last(data_dir: &Path, expected: &SessionRecord) -> io::Result<bool> {
+    match read_log(data_dir)?.as_deref().and_then(without_last_row) {
+        Some((contents, record)) if record == *expected => fs::write(data_dir.join("sessions.csv"), contents).map(|()| true),
+        _ => Ok(false),
+    }
+}
+
+fn read_log(data_dir: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(data_dir.join("sessions.csv")) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Splits the last row that parses off a log, returning the rest of the log and that row.
+fn without_last_row(contents: &str) -> Option<(String, SessionRecord)> {
+    let mut rows = rows_of(contents);
+    let (i, record) = rows.iter().enumerate().skip(1).rev().find_map(|(i, row)| parse_row(row).map(|record| (i, record)))?;
+    rows.remove(i);
+    Some((rows.iter().map(|row| format!("{}\n", row)).collect(), record))
+}
+
+/// Reads back the sessions that ended on `date`, from rotated logs too. Rows that can't be
+/// parsed are skipped, and columns added to the log later default to empty for older rows.
+pub fn read_day(data_dir: &Path, date: NaiveDate) -> io::Result<Vec<SessionRecord>> {
+    read_matching(data_dir, |record| record.ended_at.date_naive() == date)
+}
+
+/// Completed work sessions on one day.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WorkDay {
+    pub completed: u32,
+    /// Those of them that were never paused.
+    pub perfect: u32,
+}
+
+/// Completed work sessions for each day from `since` on. Days without any are left out.
+pub fn completed_work_per_day(data_dir: &Path, since: NaiveDate) -> io::Result<HashMap<NaiveDate, WorkDay>> {
+    let records = read_matching(data_dir, |record| record.completed && record.mode == Mode::Work && record.kind == RecordKind::Session && record.ended_at.date_naive() >= since)?;
+    let mut days: HashMap<NaiveDate, WorkDay> = HashMap::new();
+    for record in records {
+        let day = days.entry(record.ended_at.date_naive()).or_default();
+        day.completed += 1;
+        day.perfect += u32::from(!record.paused);
+    }
+    Ok(days)
+}
+
+/// Every record in the current and rotated logs.
+pub fn read_all(data_dir: &Path) -> io::Result<Vec<SessionRecord>> {
+    read_matching(data_dir, |_| true)
+}
+
+/// Every record in the current and rotated logs that `keep` accepts.
+fn read_matching(data_dir: &Path, keep: impl Fn(&SessionRecord) -> bool) -> io::Result<Vec<SessionRecord>> {
+    let mut paths = match rotated_files(data_dir) {
+        Ok(paths) => paths,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    paths.push(data_dir.join("sessions.csv"));
+    let mut records = Vec::new();
+    for path in paths {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        records.extend(rows_of(&contents).iter().skip(1).filter_map(|row| parse_row(row)).filter(|record| keep(record)));
+    }
+    Ok(records)
+}
+
+fn parse_row(line: &str) -> Option<SessionRecord> {
+    let fields = split_row(line);
+    let field = |i: usize| fields.get(i).map(String::as_str);
+    let time = |i| DateTime::parse_from_rfc3339(field(i)?).ok().map(|time| time.with_timezone(&Local));
+    let secs = |i| field(i)?.parse().ok().map(Duration::from_secs);
+    Some(SessionRecord {
+        started_at: time(0)?,
+        ended_at: time(1)?,
+        mode: Mode::from_key(field(2)?)?,
+        planned: secs(3)?,
+        actual: secs(4)?,
+        completed: field(5)?.parse().ok()?,
+        kind: RecordKind::from_tag(field(6).unwrap_or_default()),
+        note: field(7).unwrap_or_default().to_string(),
+        paused: field(8) != Some("false"),
+    })
+}
+
+/// Splits a row on commas outside of quotes, undoing `csv_field`.
+pub fn split_row(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().expect("at least one field");
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    fields
+}
+
+/// Rounds to the nearest multiple of `step`, with exact halves going up.
+pub fn round_to_nearest(duration: Duration, step: Duration) -> Duration {
+    if step.is_zero() {
+        return duration;
+    }
+    let step_ms = step.as_millis();
+    let rounded = (duration.as_millis() + step_ms / 2) / step_ms * step_ms;
+    Duration::from_millis(rounded as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIVE_MINUTES: Duration = Duration::from_secs(5 * 60);
+
+    fn minutes_seconds(minutes: u64, seconds: u64) -> Duration {
+        Duration::from_secs(minutes * 60 + seconds)
+    }
+
+    /// An empty directory of its own for a test to log into.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rusty-pomodoro-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("a scratch directory");
+        dir
+    }
+
+    fn exact() -> Rounding {
+        Rounding { step: Duration::ZERO, target: RoundingTarget::Actual }
+    }
+
+    fn never() -> Rotation {
+        Rotation { max_bytes: 0, max_age: Duration::ZERO, keep: Duration::ZERO }
+    }
+
+    #[test]
+    fn rounds_to_nearest_step() {
+        assert_eq!(round_to_nearest(minutes_seconds(2, 29), FIVE_MINUTES), Duration::ZERO);
+        assert_eq!(round_to_nearest(minutes_seconds(2, 30), FIVE_MINUTES), FIVE_MINUTES);
+        assert_eq!(round_to_nearest(minutes_seconds(7, 29), FIVE_MINUTES), FIVE_MINUTES);
+        assert_eq!(round_to_nearest(minutes_seconds(7, 30), FIVE_MINUTES), minutes_seconds(10, 0));
+        assert_eq!(round_to_nearest(minutes_seconds(25, 0), FIVE_MINUTES), minutes_seconds(25, 0));
+    }
+
+    #[test]
+    fn zero_step_disables_rounding() {
+        assert_eq!(round_to_nearest(minutes_seconds(12, 34), Duration::ZERO), minutes_seconds(12, 34));
+    }
+
+    #[test]
+    fn rounding_only_touches_the_chosen_duration() {
+        let now = Local::now();
+        let record = SessionRecord { started_at: now, ended_at: now, mode: Mode::Work, planned: minutes_seconds(25, 0), actual: minutes_seconds(12, 40), completed: false, kind: RecordKind::Session, note: String::new(), paused: false };
+        let row = |target| record.csv_row(Rounding { step: FIVE_MINUTES, target });
+        assert!(row(RoundingTarget::Planned).ends_with(",work,1500,760,false,,,false"));
+        assert!(row(RoundingTarget::Actual).ends_with(",work,1500,900,false,,,false"));
+        assert!(row(RoundingTarget::Both).ends_with(",work,1500,900,false,,,false"));
+    }
+
+    #[test]
+    fn notes_are_quoted_when_needed() {
+        assert_eq!(csv_field("wrote the parser"), "wrote the parser");
+        assert_eq!(csv_field("parser, lexer"), "\"parser, lexer\"");
+        assert_eq!(csv_field("the \"fun\" part"), "\"the \"\"fun\"\" part\"");
+    }
+
+    #[test]
+    fn the_tag_column_tells_sessions_tests_and_gaps_apart() {
+        let now = Local::now();
+        for kind in [RecordKind::Session, RecordKind::Test, RecordKind::Gap, RecordKind::Extension] {
+            let record = SessionRecord { started_at: now, ended_at: now, mode: Mode::Work, planned: Duration::ZERO, actual: minutes_seconds(4, 0), completed: false, kind, note: String::from("screen locked"), paused: true };
+            let parsed = parse_row(&record.csv_row(Rounding { step: Duration::ZERO, target: RoundingTarget::Actual })).expect("a valid row");
+            assert_eq!(parsed.kind, kind);
+            assert_eq!(parsed.note, "screen locked");
+        }
+    }
+
+    #[test]
+    fn logs_with_an_older_header_are_brought_up_to_date() {
+        let first = "2024-03-01T09:00:00+00:00,2024-03-01T09:25:00+00:00,work,1500,1500,true";
+        let tagged = "2024-03-02T09:00:00+00:00,2024-03-02T09:00:05+00:00,work,5,5,true,test";
+        let log = format!("started_at,ended_at,mode,planned_secs,actual_secs,completed\n{}\n", first);
+        assert_eq!(upgraded(&log), Some(format!("{}\n{},,,\n", HEADER, first)));
+        let log = format!("started_at,ended_at,mode,planned_secs,actual_secs,completed,tag\n{}\n{}\n", first, tagged);
+        let current = upgraded(&log).expect("an older header");
+        assert_eq!(current, format!("{}\n{},,,\n{},,\n", HEADER, first, tagged));
+        let rows: Vec<SessionRecord> = current.lines().skip(1).filter_map(parse_row).collect();
+        assert_eq!(rows.iter().map(|record| record.kind).collect::<Vec<_>>(), [RecordKind::Session, RecordKind::Test]);
+
+        let noted = "started_at,ended_at,mode,planned_secs,actual_secs,completed,tag,note\n";
+        let note = format!("{}{},,\"two\nlines\"\n", noted, first);
+        assert_eq!(upgraded(&note), Some(format!("{}\n{},,\"two\nlines\",\n", HEADER, first)));
+        assert!(rows.iter().all(|record| record.paused));
+        assert_eq!(upgraded("date,minutes\n2024-03-01,25\n"), None);
+        assert_eq!(rows_of("a,\"b\nc\"\nd"), ["a,\"b\nc\"", "d"]);
+    }
+
+    #[test]
+    fn rotation_moves_a_full_log_aside_and_prunes_only_its_own_files() {
+        let dir = scratch_dir("rotate");
+        let record = SessionRecord { started_at: Local::now(), ended_at: Local::now(), mode: Mode::Work, planned: FIVE_MINUTES, actual: FIVE_MINUTES, completed: true, kind: RecordKind::Session, note: String::new(), paused: false };
+        let tiny = Rotation { max_bytes: 1, ..never() };
+        for _ in 0..3 {
+            append(&dir, &record, exact(), tiny).unwrap();
+        }
+        let date = record.ended_at.date_naive();
+        assert!(dir.join(format!("sessions-{}.csv", date)).exists() && dir.join(format!("sessions-{}-2.csv", date)).exists());
+        assert_eq!(read_all(&dir).unwrap().len(), 3);
+
+        let old = dir.join("sessions-2020-01-01.csv");
+        let unrelated = dir.join("sessions-export.csv");
+        let month_ago = SystemTime::now() - Duration::from_secs(30 * 24 * 3600);
+        for path in [&old, &unrelated] {
+            fs::write(path, HEADER).unwrap();
+            fs::File::options().write(true).open(path).unwrap().set_modified(month_ago).unwrap();
+        }
+        append(&dir, &record, exact(), Rotation { keep: Duration::from_secs(24 * 3600), ..never() }).unwrap();
+        assert!(!old.exists() && unrelated.exists());
+        assert!(dir.join(format!("sessions-{}.csv", date)).exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn rotated_names_are_told_apart_from_other_files() {
+        assert!(is_rotated_name("sessions-2024-03-01.csv") && is_rotated_name("sessions-2024-03-01-12.csv"));
+        for name in ["sessions-export.csv", "sessions-2024-03-01-.csv", "sessions-2024-03-01-x.csv", "sessions-2024-13-01.csv", "sessions.csv", "sessions-\u{e9}t\u{e9}.csv"] {
+            assert!(!is_rotated_name(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn only_the_row_that_was_shown_is_removed() {
+        let dir = scratch_dir("remove-last");
+        let record = |note: &str| SessionRecord { started_at: Local::now(), ended_at: Local::now(), mode: Mode::Work, planned: FIVE_MINUTES, actual: FIVE_MINUTES, completed: true, kind: RecordKind::Session, note: note.to_string(), paused: false };
+        append(&dir, &record("first"), exact(), never()).unwrap();
+        let shown = last(&dir).unwrap().expect("a row");
+        append(&dir, &record("logged meanwhile"), exact(), never()).unwrap();
+        assert!(!remove_last(&dir, &shown).unwrap());
+        assert_eq!(read_all(&dir).unwrap().len(), 2);
+        let shown = last(&dir).unwrap().expect("a row");
+        assert!(remove_last(&dir, &shown).unwrap());
+        assert_eq!(read_all(&dir).unwrap().iter().map(|record| record.note.as_str()).collect::<Vec<_>>(), ["first"]);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn removing_the_last_row_keeps_the_rest() {
+        let now = Local::now();
+        let row = |note: &str| SessionRecord { started_at: now, ended_at: now, mode: Mode::Work, planned: FIVE_MINUTES, actual: FIVE_MINUTES, completed: true, kind: RecordKind::Session, note: note.to_string(), paused: false }
+            .csv_row(Rounding { step: Duration::ZERO, target: RoundingTarget::Actual });
+        let log = format!("{}\n{}\n{}\nnot a row\n", HEADER, row("first"), row("second"));
+        let (rest, removed) = without_last_row(&log).expect("a row to remove");
+        assert_eq!(removed.note, "second");
+        assert_eq!(rest, format!("{}\n{}\nnot a row\n", HEADER, row("first")));
+        let (rest, _) = without_last_row(&rest).expect("a row to remove");
+        assert!(without_last_row(&rest).is_none());
+
+        let log = format!("{}\n{}\n{}\n", HEADER, row("first"), row("two\nlines"));
+        let (rest, removed) = without_last_row(&log).expect("a row to remove");
+        assert_eq!((removed.note.as_str(), removed.paused), ("two\nlines", false));
+        assert_eq!(rest, format!("{}\n{}\n", HEADER, row("first")));
+    }
+
+    #[test]
+    fn days_count_their_completed_and_unpaused_work() {
+        let dir = scratch_dir("per-day");
+        let today = Local::now();
+        let record = |completed, paused| SessionRecord { started_at: today, ended_at: today, mode: Mode::Work, planned: FIVE_MINUTES, actual: FIVE_MINUTES, completed, kind: RecordKind::Session, note: String::new(), paused };
+        for (completed, paused) in [(true, false), (true, true), (false, false), (true, false)] {
+            append(&dir, &record(completed, paused), exact(), never()).unwrap();
+        }
+        let days = completed_work_per_day(&dir, today.date_naive()).unwrap();
+        assert_eq!(days.get(&today.date_naive()), Some(&WorkDay { completed: 3, perfect: 2 }));
+        assert!(completed_work_per_day(&dir, today.date_naive().succ_opt().unwrap()).unwrap().is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn notes_over_several_lines_read_back_whole() {
+        let dir = scratch_dir("multi-line");
+        let record = SessionRecord { started_at: Local::now(), ended_at: Local::now(), mode: Mode::Work, planned: FIVE_MINUTES, actual: FIVE_MINUTES, completed: true, kind: RecordKind::Session, note: String::from("read \"the\" spec,\nthen wrote"), paused: false };
+        append(&dir, &record, exact(), never()).unwrap();
+        append(&dir, &record, exact(), never()).unwrap();
+        assert_eq!(read_all(&dir).unwrap(), [record.clone(), record]);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
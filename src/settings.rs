@@ -0,0 +1,53 @@
+use crate::{config::{MAX_UI_SCALE, MIN_UI_SCALE}, sound::SoundTheme, Pomodoro, PomodoroMessage, Screen, MIN_OPACITY};
+use iced::{widget::{Button, Column, Container, PickList, Row, Slider, Text, TextInput}, Alignment, Element, Length};
+
+impl Pomodoro {
+    pub(crate) fn settings_view(&self) -> Element<'_, PomodoroMessage> {
+        let sound_theme = Row::new().spacing(10).align_items(Alignment::Center)
+            .push(Text::new("Completion sound").width(Length::Fixed(160.0)))
+            .push(PickList::new(&SoundTheme::ALL[..], Some(self.config.sound_theme), PomodoroMessage::SetSoundTheme).width(Length::Fill));
+        let custom_sound = Row::new().spacing(10).align_items(Alignment::Center)
+            .push(Text::new("Custom file").width(Length::Fixed(160.0)))
+            .push(TextInput::new("Path to a sound file", &self.config.custom_sound).on_input(PomodoroMessage::SetCustomSound).on_submit(PomodoroMessage::SaveConfig));
+        let previews = SoundTheme::ALL.into_iter().filter(|theme| *theme != SoundTheme::Silent).fold(Row::new().spacing(5), |row, theme| {
+            row.push(Button::new(Text::new(format!("Preview {}", theme))).on_press(PomodoroMessage::PreviewSound(theme).pressed()))
+        });
+        let opacity = Row::new().spacing(10).align_items(Alignment::Center)
+            .push(Text::new("Opacity").width(Length::Fixed(160.0)))
+            .push(Slider::new(MIN_OPACITY..=1.0, self.config.opacity, PomodoroMessage::SetOpacity).step(0.05).width(Length::Fill))
+            .push(Text::new(format!("{:.0}%", self.config.opacity * 100.0)).width(Length::Fixed(50.0)));
+        let ui_scale = Row::new().spacing(10).align_items(Alignment::Center)
+            .push(Text::new("Zoom").width(Length::Fixed(160.0)))
+            .push(Slider::new(MIN_UI_SCALE..=MAX_UI_SCALE, self.config.ui_scale, PomodoroMessage::SetUiScale).step(0.05).width(Length::Fill))
+            .push(Text::new(format!("{:.0}%", self.config.ui_scale * 100.0)).width(Length::Fixed(50.0)));
+        let test_session = Row::new().spacing(10).align_items(Alignment::Center)
+            .push(Button::new("Run 5s test session").on_press(PomodoroMessage::StartTestSession.pressed()))
+            .push(Text::new("Goes through the whole completion path; logged with a \"test\" tag").size(14));
+        let export = Row::new().spacing(10).align_items(Alignment::Center)
+            .push(Button::new("Export today as .ics").on_press(PomodoroMessage::ExportCalendar.pressed()))
+            .push(Button::new("Heatmap").on_press(PomodoroMessage::ShowScreen(Screen::Heatmap).pressed()))
+            .push_maybe(self.export_status.as_deref().map(|status| Text::new(status).size(14)));
+        let import = Row::new().spacing(10).align_items(Alignment::Center)
+            .push(TextInput::new("date,mode,duration CSV from another app", &self.import_path).on_input(PomodoroMessage::SetImportPath).on_submit(PomodoroMessage::ImportHistory).width(Length::Fill))
+            .push(Button::new("Import history").on_press_maybe((!self.import_path.trim().is_empty()).then_some(PomodoroMessage::ImportHistory).map(PomodoroMessage::pressed)))
+            .push_maybe(self.import_status.as_deref().map(|status| Text::new(status).size(14)));
+        let remove_last = match &self.removing {
+            Some(record) => Row::new().spacing(10).align_items(Alignment::Center)
+                .push(Text::new(format!("Remove the {} session from {} to {}?", self.config.label_for(record.mode).to_lowercase(), record.started_at.format("%H:%M"), record.ended_at.format("%H:%M"))).size(14))
+                .push(Button::new("Remove").on_press(PomodoroMessage::ConfirmRemoveLast(true).pressed()))
+                .push(Button::new("Keep it").on_press(PomodoroMessage::ConfirmRemoveLast(false).pressed())),
+            None => Row::new().spacing(10).align_items(Alignment::Center)
+                .push(Button::new("Remove last session").on_press(PomodoroMessage::RemoveLastSession.pressed()))
+                .push_maybe(self.removal_status.as_deref().map(|status| Text::new(status).size(14))),
+        };
+        let focus_mode = Row::new().spacing(10).align_items(Alignment::Center)
+            .push(Button::new(if self.config.focus_mode { "Focus mode: on" } else { "Focus mode: off" }).on_press(PomodoroMessage::ToggleFocusMode.pressed()))
+            .push(Text::new("Runs focus_block_cmd while work is running, and focus_unblock_cmd after").size(14));
+        let day_totals = Row::new().spacing(10).align_items(Alignment::Center)
+            .push(Button::new(if self.config.show_day_totals { "Day totals: on" } else { "Day totals: off" }).on_press(PomodoroMessage::ToggleDayTotals.pressed()))
+            .push(Text::new("Shows today's time in work and in breaks under the timer").size(14));
+        let back_button = Button::new("Back").on_press(PomodoroMessage::ShowScreen(Screen::Timer).pressed());
+        let col = Column::new().spacing(15).padding(20).push(sound_theme).push(custom_sound).push(previews).push(opacity).push(ui_scale).push(test_session).push(export).push(import).push(remove_last).push(focus_mode).push(day_totals).push(back_button);
+        Container::new(col).width(Length::Fill).height(Length::Fill).into()
+    }
+}
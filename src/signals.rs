@@ -0,0 +1,20 @@
+use crate::PomodoroMessage;
+use iced::{futures::{channel::mpsc, SinkExt, StreamExt}, subscription, Subscription};
+
+/// Turns Ctrl+C in the launching terminal into a regular, graceful shutdown.
+pub fn interrupts() -> Subscription<PomodoroMessage> {
+    struct Interrupts;
+
+    subscription::channel(std::any::TypeId::of::<Interrupts>(), 1, |mut output| async move {
+        let (sender, mut interrupts) = mpsc::unbounded();
+        if let Err(err) = ctrlc::set_handler(move || {
+            let _ = sender.unbounded_send(());
+        }) {
+            eprintln!("Could not install Ctrl+C handler: {}", err);
+        }
+        while interrupts.next().await.is_some() {
+            let _ = output.send(PomodoroMessage::Shutdown).await;
+        }
+        iced::futures::future::pending().await
+    })
+}
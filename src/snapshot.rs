@@ -0,0 +1,61 @@
+use crate::Mode;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::{Path, PathBuf}, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SavedState {
+    Idle,
+    /// Paused by something other than the user, or on its way back from a pause.
+    Paused,
+    /// Paused with the Pause button or an equivalent.
+    PausedManually,
+    Running,
+    /// Waiting at 00:00 for the completion notification to be dismissed.
+    Held,
+}
+
+/// The timer as it was last saved, so a session survives the app being closed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub mode: Mode,
+    pub state: SavedState,
+    /// Kept in milliseconds rather than seconds, so a session resumes at the exact spot it was paused.
+    pub remaining_ms: u64,
+    pub session_total_ms: u64,
+    pub completed_work_sessions: u32,
+    /// Wall-clock time of the save, in seconds since the Unix epoch.
+    pub saved_at: u64,
+}
+
+impl Snapshot {
+    fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join("snapshot.toml")
+    }
+
+    pub fn load(data_dir: &Path) -> Option<Snapshot> {
+        let contents = fs::read_to_string(Snapshot::path(data_dir)).ok()?;
+        toml::from_str(&contents).map_err(|err| eprintln!("Ignoring invalid snapshot: {}", err)).ok()
+    }
+
+    pub fn save(&self, data_dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(data_dir)?;
+        let contents = toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(Snapshot::path(data_dir), contents)
+    }
+
+    /// Local calendar day the snapshot was taken on.
+    pub fn saved_on(&self) -> Option<NaiveDate> {
+        DateTime::from_timestamp(self.saved_at as i64, 0).map(|saved| saved.with_timezone(&Local).date_naive())
+    }
+
+    /// Real time that has passed since the snapshot was taken.
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(unix_now().saturating_sub(self.saved_at))
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
@@ -0,0 +1,136 @@
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, io, path::{Path, PathBuf}, process, sync::mpsc, thread};
+
+/// Sound played when a session completes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SoundTheme {
+    Silent,
+    Bell,
+    Chime,
+    Digital,
+    /// A user-provided file, see `Config::custom_sound`.
+    Custom,
+}
+
+impl SoundTheme {
+    pub const ALL: [SoundTheme; 5] = [SoundTheme::Silent, SoundTheme::Bell, SoundTheme::Chime, SoundTheme::Digital, SoundTheme::Custom];
+
+    fn bundled(self) -> Option<(&'static str, &'static [u8])> {
+        match self {
+            SoundTheme::Bell => Some(("bell.wav", include_bytes!("../assets/sounds/bell.wav"))),
+            SoundTheme::Chime => Some(("chime.wav", include_bytes!("../assets/sounds/chime.wav"))),
+            SoundTheme::Digital => Some(("digital.wav", include_bytes!("../assets/sounds/digital.wav"))),
+            SoundTheme::Silent | SoundTheme::Custom => None,
+        }
+    }
+
+    /// Resolves the theme to a file on disk, unpacking bundled sounds into the cache directory.
+    pub fn path(self, custom: &str) -> io::Result<Option<PathBuf>> {
+        match self {
+            SoundTheme::Silent => Ok(None),
+            SoundTheme::Custom if custom.is_empty() => Ok(None),
+            SoundTheme::Custom => Ok(Some(PathBuf::from(custom))),
+            theme => {
+                let (name, bytes) = theme.bundled().expect("bundled theme");
+                unpack(name, bytes).map(Some)
+            }
+        }
+    }
+}
+
+/// Writes a bundled sound into the user's own cache directory for the system player to open.
+/// It's written again whenever what's there isn't the bundled sound.
+fn unpack(name: &str, bytes: &[u8]) -> io::Result<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "rusty-pomodoro").ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no cache directory to unpack sounds into"))?;
+    let dir = dirs.cache_dir().join("sounds");
+    let path = dir.join(name);
+    if fs::read(&path).ok().as_deref() != Some(bytes) {
+        fs::create_dir_all(&dir)?;
+        fs::write(&path, bytes)?;
+    }
+    Ok(path)
+}
+
+impl fmt::Display for SoundTheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SoundTheme::Silent => "None",
+            SoundTheme::Bell => "Bell",
+            SoundTheme::Chime => "Chime",
+            SoundTheme::Digital => "Digital",
+            SoundTheme::Custom => "Custom file",
+        })
+    }
+}
+
+/// Plays the theme's sound in the background without waiting for it to finish.
+pub fn play(theme: SoundTheme, custom: &str) {
+    match theme.path(custom) {
+        Ok(Some(path)) => {
+            thread::spawn(move || {
+                if let Err(err) = play_file(&path) {
+                    eprintln!("Could not play {}: {}", path.display(), err);
+                }
+            });
+        }
+        Ok(None) => {}
+        Err(err) => eprintln!("Could not prepare sound: {}", err),
+    }
+}
+
+/// Plays the `click_feedback` click from one helper thread that unpacks it once. A click
+/// that comes while the last one is still playing is dropped, rather than starting another
+/// player next to it.
+#[derive(Default)]
+pub struct Clicker {
+    clicks: Option<mpsc::SyncSender<()>>,
+}
+
+impl Clicker {
+    pub fn click(&mut self) {
+        let clicks = self.clicks.get_or_insert_with(|| {
+            // No buffer, so a send only gets through while the thread is waiting for one
+            let (clicks, pressed) = mpsc::sync_channel(0);
+            thread::spawn(move || {
+                let path = match unpack("click.wav", include_bytes!("../assets/sounds/click.wav")) {
+                    Ok(path) => path,
+                    Err(err) => return eprintln!("Could not prepare sound: {}", err),
+                };
+                for () in pressed {
+                    let _ = play_file(&path);
+                }
+            });
+            clicks
+        });
+        let _ = clicks.try_send(());
+    }
+}
+
+/// Hands the file to the first system player that is installed.
+fn play_file(path: &Path) -> io::Result<()> {
+    let mut players = Vec::new();
+    if cfg!(target_os = "macos") {
+        players.push(process::Command::new("afplay"));
+    } else if cfg!(windows) {
+        let mut powershell = process::Command::new("powershell");
+        powershell.args(["-NoProfile", "-Command"]).arg(format!("(New-Object Media.SoundPlayer '{}').PlaySync()", path.display()));
+        players.push(powershell);
+    } else {
+        players.extend(["paplay", "pw-play", "aplay"].map(process::Command::new));
+    }
+    let mut last_err = io::Error::new(io::ErrorKind::NotFound, "no audio player found");
+    for mut player in players {
+        if !cfg!(windows) {
+            player.arg(path);
+        }
+        match player.stdout(process::Stdio::null()).stderr(process::Stdio::null()).status() {
+            Ok(status) if status.success() => return Ok(()),
+            // Installed but unable to play it, e.g. without a sound server; the next may manage
+            Ok(status) => last_err = io::Error::other(format!("{:?} exited with {}", player.get_program(), status)),
+            Err(err) => last_err = err,
+        }
+    }
+    Err(last_err)
+}
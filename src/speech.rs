@@ -0,0 +1,61 @@
+use std::{io, process, sync::atomic::{AtomicBool, Ordering}, thread, time::Duration};
+
+/// Set while an announcement is being spoken, so a second one is dropped instead of piling up.
+static SPEAKING: AtomicBool = AtomicBool::new(false);
+
+/// Speaks `text` in the background through the system's speech synthesizer (`spd-say` or
+/// `espeak` on Linux, `say` on macOS, System.Speech on Windows). Does nothing if an earlier
+/// announcement is still playing.
+pub fn say(text: String) {
+    if SPEAKING.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    thread::spawn(move || {
+        if let Err(err) = speak(&text) {
+            eprintln!("Could not announce {:?}: {}", text, err);
+        }
+        SPEAKING.store(false, Ordering::Release);
+    });
+}
+
+/// Hands the text to the first speech synthesizer that is installed and waits for it.
+fn speak(text: &str) -> io::Result<()> {
+    let mut speakers = Vec::new();
+    if cfg!(target_os = "macos") {
+        speakers.push(process::Command::new("say"));
+    } else if cfg!(windows) {
+        let mut powershell = process::Command::new("powershell");
+        powershell.args(["-NoProfile", "-Command"]).arg(format!(
+            "Add-Type -AssemblyName System.Speech; (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak('{}')",
+            text.replace('\'', "''")
+        ));
+        speakers.push(powershell);
+    } else {
+        let mut spd_say = process::Command::new("spd-say");
+        spd_say.arg("--wait");
+        speakers.extend([spd_say, process::Command::new("espeak")]);
+    }
+    let mut last_err = io::Error::new(io::ErrorKind::NotFound, "no speech synthesizer found");
+    for mut speaker in speakers {
+        if !cfg!(windows) {
+            speaker.arg(text);
+        }
+        match speaker.stdout(process::Stdio::null()).stderr(process::Stdio::null()).status() {
+            Ok(status) if status.success() => return Ok(()),
+            // Installed but not working, e.g. spd-say without a running speech-dispatcher
+            Ok(status) => last_err = io::Error::other(format!("{:?} exited with {}", speaker.get_program(), status)),
+            Err(err) => last_err = err,
+        }
+    }
+    Err(last_err)
+}
+
+/// Phrase for an announcement with `remaining` left, e.g. "5 minutes remaining".
+pub fn remaining(remaining: Duration) -> String {
+    let minutes = (remaining.as_secs() + 30) / 60;
+    match minutes {
+        0 => format!("{} seconds remaining", remaining.as_secs()),
+        1 => String::from("1 minute remaining"),
+        minutes => format!("{} minutes remaining", minutes),
+    }
+}
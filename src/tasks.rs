@@ -0,0 +1,105 @@
+use crate::{Pomodoro, PomodoroMessage, Screen};
+use iced::{widget::{Button, Column, Container, Row, Scrollable, Text, TextInput}, Alignment, Element, Length};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::{Path, PathBuf}};
+
+/// Something to work on, with the number of work sessions it's expected to take.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    pub name: String,
+    pub estimate: u32,
+    /// Work sessions completed while this was the current task.
+    #[serde(default)]
+    pub completed: u32,
+}
+
+impl Task {
+    pub fn is_done(&self) -> bool {
+        self.completed >= self.estimate
+    }
+}
+
+/// Every task along with the one being worked on, persisted in `tasks.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tasks {
+    /// Index into `tasks` of the task that completed work sessions count towards.
+    pub current: Option<usize>,
+    pub tasks: Vec<Task>,
+}
+
+impl Tasks {
+    fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join("tasks.toml")
+    }
+
+    pub fn load(data_dir: &Path) -> Tasks {
+        let mut tasks: Tasks = fs::read_to_string(Tasks::path(data_dir))
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).map_err(|err| eprintln!("Ignoring invalid tasks: {}", err)).ok())
+            .unwrap_or_default();
+        tasks.current = tasks.current.filter(|&i| i < tasks.tasks.len());
+        tasks
+    }
+
+    pub fn save(&self, data_dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(data_dir)?;
+        let contents = toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(Tasks::path(data_dir), contents)
+    }
+
+    pub fn current(&self) -> Option<&Task> {
+        self.tasks.get(self.current?)
+    }
+
+    /// Counts a completed work session towards the current task. Returns the task if this was
+    /// the session that finished it.
+    pub fn count_session(&mut self) -> Option<&Task> {
+        let task = self.tasks.get_mut(self.current?)?;
+        let was_done = task.is_done();
+        task.completed += 1;
+        (!was_done && task.is_done()).then_some(&*task)
+    }
+
+    /// Takes a work session removed from the log back off the current task.
+    pub fn uncount_session(&mut self) {
+        if let Some(task) = self.current.and_then(|i| self.tasks.get_mut(i)) {
+            task.completed = task.completed.saturating_sub(1);
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index >= self.tasks.len() {
+            return;
+        }
+        self.tasks.remove(index);
+        self.current = match self.current {
+            Some(current) if current == index => None,
+            Some(current) if current > index => Some(current - 1),
+            current => current,
+        };
+    }
+}
+
+impl Pomodoro {
+    pub(crate) fn tasks_view(&self) -> Element<'_, PomodoroMessage> {
+        let list = self.tasks.tasks.iter().enumerate().fold(Column::new().spacing(5), |col, (i, task)| {
+            let select = match self.tasks.current == Some(i) {
+                true => Button::new("Current").on_press(PomodoroMessage::SelectTask(None).pressed()),
+                false => Button::new("Work on this").on_press(PomodoroMessage::SelectTask(Some(i)).pressed()),
+            };
+            let row = Row::new().spacing(10).align_items(Alignment::Center)
+                .push(Text::new(format!("{} \u{2014} {} of {} done", task.name, task.completed, task.estimate)).width(Length::Fill))
+                .push(select)
+                .push(Button::new("Remove").on_press(PomodoroMessage::RemoveTask(i).pressed()));
+            col.push(row)
+        });
+        let form = Row::new().spacing(5).align_items(Alignment::Center)
+            .push(TextInput::new("New task", &self.task_name_input).on_input(PomodoroMessage::SetTaskName).on_submit(PomodoroMessage::AddTask).width(Length::Fill))
+            .push(TextInput::new("Pomodoros", &self.task_estimate_input).on_input(PomodoroMessage::SetTaskEstimate).on_submit(PomodoroMessage::AddTask).width(Length::Fixed(100.0)))
+            .push(Button::new("Add").on_press(PomodoroMessage::AddTask.pressed()));
+        let back_button = Button::new("Back").on_press(PomodoroMessage::ShowScreen(Screen::Timer).pressed());
+        let col = Column::new().spacing(15).padding(20).push(Scrollable::new(list).height(Length::Fill)).push(form).push(back_button);
+        Container::new(col).width(Length::Fill).height(Length::Fill).into()
+    }
+}
@@ -0,0 +1,97 @@
+use crate::Mode;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Length of every mode's session.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Durations {
+    pub work: Duration,
+    pub short_break: Duration,
+    pub long_break: Duration,
+}
+
+impl Durations {
+    pub fn get(&self, mode: Mode) -> Duration {
+        match mode {
+            Mode::Work => self.work,
+            Mode::ShortBreak => self.short_break,
+            Mode::LongBreak => self.long_break,
+        }
+    }
+
+    pub fn set(&mut self, mode: Mode, duration: Duration) {
+        match mode {
+            Mode::Work => self.work = duration,
+            Mode::ShortBreak => self.short_break = duration,
+            Mode::LongBreak => self.long_break = duration,
+        }
+    }
+}
+
+/// A timing technique: session lengths plus the rule for when a long break comes up.
+/// Custom techniques are declared in the config with the same fields.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Technique {
+    pub work_minutes: u64,
+    pub short_break_minutes: u64,
+    pub long_break_minutes: u64,
+    /// Work sessions before a long break is due. `None` only ever takes short breaks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sessions_before_long_break: Option<u32>,
+}
+
+impl Technique {
+    /// 25 minutes of work, 5 minutes off, and a long break every fourth session.
+    pub fn classic() -> Technique {
+        Technique { work_minutes: 25, short_break_minutes: 5, long_break_minutes: 15, sessions_before_long_break: Some(4) }
+    }
+
+    /// 52 minutes of work followed by a 17 minute break, with no long breaks.
+    pub fn fifty_two_seventeen() -> Technique {
+        Technique { work_minutes: 52, short_break_minutes: 17, long_break_minutes: 17, sessions_before_long_break: None }
+    }
+
+    /// 90 minute ultradian cycles with a 20 minute rest after each.
+    pub fn ultradian() -> Technique {
+        Technique { work_minutes: 90, short_break_minutes: 20, long_break_minutes: 20, sessions_before_long_break: None }
+    }
+
+    /// Looks up a built-in technique by the name used in the config.
+    pub fn preset(name: &str) -> Option<Technique> {
+        match name {
+            "classic" => Some(Technique::classic()),
+            "52/17" => Some(Technique::fifty_two_seventeen()),
+            "ultradian" => Some(Technique::ultradian()),
+            _ => None,
+        }
+    }
+
+    /// Session lengths in full. Absurd minute counts saturate rather than overflow, so that
+    /// `validate_durations` gets to reject them.
+    pub fn durations(&self) -> Durations {
+        let minutes = |minutes: u64| Duration::from_secs(minutes.saturating_mul(60));
+        Durations {
+            work: minutes(self.work_minutes),
+            short_break: minutes(self.short_break_minutes),
+            long_break: minutes(self.long_break_minutes),
+        }
+    }
+
+    /// Work sessions, counting one in progress, still to finish before the next long break.
+    /// `None` for techniques without long breaks.
+    pub fn sessions_until_long_break(&self, completed_work_sessions: u32) -> Option<u32> {
+        let every = self.sessions_before_long_break.filter(|&every| every > 0)?;
+        Some(every - completed_work_sessions % every)
+    }
+
+    /// The mode that follows `finished`, given how many work sessions have been completed so far.
+    pub fn next_mode(&self, finished: Mode, completed_work_sessions: u32) -> Mode {
+        match finished {
+            Mode::Work => match self.sessions_before_long_break {
+                Some(every) if every > 0 && completed_work_sessions.is_multiple_of(every) => Mode::LongBreak,
+                _ => Mode::ShortBreak,
+            },
+            Mode::ShortBreak | Mode::LongBreak => Mode::Work,
+        }
+    }
+}
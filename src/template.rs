@@ -0,0 +1,118 @@
+use std::fmt;
+
+/// What a completion message can fill in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variable {
+    /// The name of the mode that finished, from `labels` when set.
+    Mode,
+    /// How long the session lasted, like "25m" or "1h30m".
+    Duration,
+    /// Work sessions completed today, this one included.
+    CountToday,
+    /// The current task's name, empty without one.
+    Task,
+}
+
+impl Variable {
+    fn from_name(name: &str) -> Option<Variable> {
+        match name {
+            "mode" => Some(Variable::Mode),
+            "duration" => Some(Variable::Duration),
+            "count_today" => Some(Variable::CountToday),
+            "task" => Some(Variable::Task),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Text(String),
+    Variable(Variable),
+}
+
+/// Why a template can't be used.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateError {
+    UnknownVariable(String),
+    Unclosed,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::UnknownVariable(name) => write!(f, "there's no {{{}}}, only {{mode}}, {{duration}}, {{count_today}} and {{task}}", name),
+            TemplateError::Unclosed => write!(f, "a {{ is never closed"),
+        }
+    }
+}
+
+/// A message with `{name}` variables in it. `{{` and `}}` stand for plain braces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Template, TemplateError> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(TemplateError::Unclosed),
+                        }
+                    }
+                    let variable = Variable::from_name(name.trim()).ok_or(TemplateError::UnknownVariable(name))?;
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    pieces.push(Piece::Variable(variable));
+                }
+                c => text.push(c),
+            }
+        }
+        pieces.push(Piece::Text(text));
+        Ok(Template { pieces })
+    }
+
+    /// The message with every variable replaced by what `value` gives for it.
+    pub fn render(&self, value: impl Fn(Variable) -> String) -> String {
+        self.pieces.iter().map(|piece| match piece {
+            Piece::Text(text) => text.clone(),
+            Piece::Variable(variable) => value(*variable),
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variables_are_filled_in_and_unknown_ones_are_refused() {
+        let template = Template::parse("Finished {duration} of {task} \u{2014} #{count_today} today! {{{mode}}}").unwrap();
+        let message = template.render(|variable| match variable {
+            Variable::Mode => String::from("Work"),
+            Variable::Duration => String::from("25m"),
+            Variable::CountToday => String::from("3"),
+            Variable::Task => String::from("Taxes"),
+        });
+        assert_eq!(message, "Finished 25m of Taxes \u{2014} #3 today! {Work}");
+
+        assert_eq!(Template::parse("Done with {project}"), Err(TemplateError::UnknownVariable(String::from("project"))));
+        assert_eq!(Template::parse("Done with {task"), Err(TemplateError::Unclosed));
+    }
+}
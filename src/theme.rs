@@ -0,0 +1,79 @@
+use crate::{config::{Config, PerMode}, Mode};
+use iced::{theme::Palette, Color, Theme};
+
+/// The dark theme, with `accent_color` as its primary color when one is set and valid.
+pub fn from_config(config: &Config) -> Theme {
+    let Some(accent) = config.accent_color.as_deref() else {
+        return Theme::Dark;
+    };
+    match parse_hex(accent) {
+        Some(primary) => Theme::custom(String::from("Dark with accent"), Palette { primary, ..Palette::DARK }),
+        None => {
+            eprintln!("Ignoring accent_color {:?}: expected a hex color like \"#e5533d\"", accent);
+            Theme::Dark
+        }
+    }
+}
+
+/// A theme for each mode when `mode_colors` is on, with the accent color carried over.
+pub fn per_mode(config: &Config) -> Option<PerMode<Theme>> {
+    if !config.mode_colors {
+        return None;
+    }
+    let theme = |mode: Mode, fallback: Color| {
+        let background = match config.mode_theme.get(mode) {
+            Some(hex) => parse_hex(hex).unwrap_or_else(|| {
+                eprintln!("Ignoring mode_theme color {:?}: expected a hex color like \"#1b1d24\"", hex);
+                fallback
+            }),
+            None => fallback,
+        };
+        let base = if luminance(background) < 0.5 { Palette::DARK } else { Palette::LIGHT };
+        let primary = config.accent_color.as_deref().and_then(parse_hex).unwrap_or(base.primary);
+        Theme::custom(format!("{} colors", mode.label()), Palette { background, primary, ..base })
+    };
+    Some(PerMode {
+        work: Some(theme(Mode::Work, Color::from_rgb8(0x1b, 0x1d, 0x24))),
+        short_break: Some(theme(Mode::ShortBreak, Color::from_rgb8(0xee, 0xf4, 0xec))),
+        long_break: Some(theme(Mode::LongBreak, Color::from_rgb8(0xe8, 0xef, 0xf7))),
+    })
+}
+
+/// Relative brightness from 0 to 1, weighting green the most as the eye does.
+fn luminance(color: Color) -> f32 {
+    0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b
+}
+
+/// Parses "#rrggbb" or "#rgb", with or without the "#".
+pub fn parse_hex(text: &str) -> Option<Color> {
+    let text = text.trim();
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    // `from_str_radix` would also take the sign in "+1+2+3"
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |range: std::ops::Range<usize>| u8::from_str_radix(hex.get(range)?, 16).ok();
+    match hex.len() {
+        6 => Some(Color::from_rgb8(channel(0..2)?, channel(2..4)?, channel(4..6)?)),
+        3 => {
+            let short = |i: usize| channel(i..i + 1).map(|c| c * 17);
+            Some(Color::from_rgb8(short(0)?, short(1)?, short(2)?))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_colors_parse_in_long_and_short_form() {
+        assert_eq!(parse_hex("#ff8000"), Some(Color::from_rgb8(255, 128, 0)));
+        assert_eq!(parse_hex(" 1A2b3C "), Some(Color::from_rgb8(0x1a, 0x2b, 0x3c)));
+        assert_eq!(parse_hex("#f80"), Some(Color::from_rgb8(255, 136, 0)));
+        for invalid in ["", "#", "##f80", "+1+2+3", "-1-2-3", "#ff80", "#ff80000", "#gg0000", "#ff 000", "#éa"] {
+            assert_eq!(parse_hex(invalid), None, "{:?}", invalid);
+        }
+    }
+}
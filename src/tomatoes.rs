@@ -0,0 +1,26 @@
+use crate::PomodoroMessage;
+use iced::{widget::{Column, Row, Text}, Alignment, Color, Element};
+
+const TOMATO: Color = Color { r: 0.9, g: 0.3, b: 0.25, a: 1.0 };
+const ICON_SIZE: f32 = 18.0;
+/// Icons that fit across the window before wrapping, with room left for the "+N".
+const PER_ROW: usize = ((crate::WINDOW_SIZE.width - 80.0) / (ICON_SIZE + 4.0)) as usize;
+
+/// One tomato per work session finished today, filled up to `completed` out of `goal`, with a
+/// "+N" once the goal is passed.
+pub fn earned(completed: u32, goal: u32) -> Element<'static, PomodoroMessage> {
+    let icons: Vec<bool> = (0..goal).map(|i| i < completed).collect();
+    let mut rows: Vec<Row<'static, PomodoroMessage>> = icons.chunks(PER_ROW).map(|chunk| {
+        chunk.iter().fold(Row::new().spacing(4).align_items(Alignment::Center), |row, &filled| {
+            row.push(Text::new(if filled { "\u{25CF}" } else { "\u{25CB}" }).size(ICON_SIZE).style(TOMATO))
+        })
+    }).collect();
+    if completed > goal {
+        let overflow = Text::new(format!("+{}", completed - goal)).size(ICON_SIZE).style(TOMATO);
+        match rows.pop() {
+            Some(last) => rows.push(last.push(overflow)),
+            None => rows.push(Row::new().push(overflow)),
+        }
+    }
+    rows.into_iter().fold(Column::new().spacing(2).align_items(Alignment::Center), |col, row| col.push(row)).into()
+}
@@ -0,0 +1,45 @@
+use crate::{persistence::{Persistence, IN_USE_FILE}, Pomodoro, PomodoroMessage};
+use iced::{widget::{Button, Column, Container, Text}, Alignment, Element, Length};
+use std::{fs, io, path::Path};
+
+const WELCOMED: &str = "welcomed";
+
+const INTRO: &[&str] = &[
+    "Work, Short break and Long Break pick what the timer counts down.",
+    "Start, Pause and Resume control the session; Reset (or R) puts the time back.",
+    "HUD (or H) shrinks the window to just the countdown.",
+    "Settings has the completion sound and a 5 second test session.",
+    "Everything else lives in config.toml, see the README.",
+];
+
+/// True on a first launch, when there is neither a config file nor any saved state yet. The
+/// running app's own in-use mark doesn't count, since it is written before this is asked.
+/// Anything saved later, including the marker left by "Get started", makes this a returning user.
+/// Without anywhere to save to, the welcome screen would show on every launch, so it never does.
+pub fn is_first_run(persistence: &Persistence) -> bool {
+    match persistence {
+        Persistence::Disk { config_file, data_dir } => {
+            let nothing_saved = fs::read_dir(data_dir).map_or(true, |entries| entries.flatten().all(|entry| entry.file_name() == IN_USE_FILE));
+            nothing_saved && !config_file.exists()
+        }
+        Persistence::Memory => false,
+    }
+}
+
+/// Leaves a marker in the data directory so the welcome screen is not shown again, even if
+/// nothing else gets saved before the app is closed.
+pub fn dismiss(data_dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(data_dir)?;
+    fs::write(data_dir.join(WELCOMED), "")
+}
+
+impl Pomodoro {
+    pub(crate) fn welcome_view(&self) -> Element<'_, PomodoroMessage> {
+        let intro = INTRO.iter().fold(Column::new().spacing(5), |col, line| col.push(Text::new(*line).size(16)));
+        let col = Column::new().spacing(15).padding(20).align_items(Alignment::Center)
+            .push(Text::new("Welcome to Rusty Pomodoro").size(28))
+            .push(intro)
+            .push(Button::new("Get started").on_press(PomodoroMessage::GetStarted));
+        Container::new(col).width(Length::Fill).height(Length::Fill).center_x().center_y().into()
+    }
+}